@@ -0,0 +1,210 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use api_models::player::Song;
+use seed::{prelude::*, *};
+use wasm_bindgen_futures::spawn_local;
+
+use crate::api;
+use crate::ui_settings::{self, UiSettings};
+use cache::CachedArt;
+
+pub mod cache;
+
+/// Where the backend should look for album art, tried in the order returned by `providers`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArtProvider {
+    /// Picture embedded in the audio file tags.
+    Embedded,
+    /// `cover.jpg` and similar files next to the audio file.
+    Folder,
+    /// Online lookup by artist and album, done by the backend.
+    Online,
+}
+
+impl ArtProvider {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Embedded => "embedded",
+            Self::Folder => "folder",
+            Self::Online => "online",
+        }
+    }
+}
+
+pub fn providers(ui_settings: &UiSettings) -> Vec<ArtProvider> {
+    let mut providers = vec![ArtProvider::Embedded, ArtProvider::Folder];
+    if ui_settings.online_album_art {
+        providers.push(ArtProvider::Online);
+    }
+    providers
+}
+
+fn names(providers: &[ArtProvider]) -> Vec<&'static str> {
+    providers.iter().map(|provider| provider.name()).collect()
+}
+
+/// Returns the url of the first image found by `providers`.
+///
+/// Results are cached per artist and album, including albums without any art,
+/// unless a provider failed and the result may be incomplete. Albums without art
+/// are looked up again once more providers are enabled.
+pub async fn resolve(track: &Song, providers: &[ArtProvider]) -> Option<String> {
+    let cache_key = cache::key(track.artist.as_deref(), track.album.as_deref());
+    let provider_names = names(providers);
+    if let Some(cached) = cache_key
+        .as_deref()
+        .and_then(|cache_key| cache::get(cache_key, &provider_names))
+    {
+        return match cached {
+            CachedArt::Found(url) => Some(url),
+            CachedArt::Missing => None,
+        };
+    }
+    let mut failed = false;
+    let mut found = None;
+    for provider in providers {
+        match api::get_album_art(provider.name(), track).await {
+            Ok(Some(url)) => {
+                found = Some(url);
+                break;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                failed = true;
+                log!("Album art lookup failed", provider.name(), e);
+            }
+        }
+    }
+    if let Some(cache_key) = cache_key {
+        if found.is_some() || !failed {
+            cache::insert(cache_key, found.clone(), &provider_names);
+        }
+    }
+    found
+}
+
+// thumbnail lookups hit the backend once per provider, a long queue must not flood it
+const MAX_CONCURRENT_LOOKUPS: usize = 4;
+
+type ThumbnailCallback = Box<dyn FnOnce(&str)>;
+
+#[derive(Default)]
+struct Lookups {
+    /// Callbacks of every album queued or being looked up, so each album is resolved once.
+    in_flight: HashMap<String, Vec<ThumbnailCallback>>,
+    queued: VecDeque<(String, Song, Vec<ArtProvider>)>,
+    workers: usize,
+}
+
+thread_local! {
+    static LOOKUPS: RefCell<Lookups> = RefCell::new(Lookups::default());
+}
+
+/// Resolves art for each distinct album in `songs`, for use as list thumbnails.
+///
+/// `on_resolved` receives the album cache key and image url of every album that has art,
+/// right away when it is cached and after the lookup otherwise. Lookups are shared with
+/// other callers waiting for the same album and at most `MAX_CONCURRENT_LOOKUPS` run at once.
+pub fn request_thumbnails<Ms: 'static>(
+    songs: &[Song],
+    orders: &mut impl Orders<Ms>,
+    on_resolved: impl FnOnce(String, String) -> Ms + Clone + 'static,
+) {
+    let providers = providers(&ui_settings::load());
+    let provider_names = names(&providers);
+    let mut requested = HashSet::new();
+    for song in songs {
+        let cache_key = match cache::key(song.artist.as_deref(), song.album.as_deref()) {
+            Some(cache_key) if requested.insert(cache_key.clone()) => cache_key,
+            _ => continue,
+        };
+        match cache::get(&cache_key, &provider_names) {
+            Some(CachedArt::Found(url)) => {
+                orders.send_msg(on_resolved.clone()(cache_key, url));
+            }
+            Some(CachedArt::Missing) => {}
+            None => {
+                let msg_sender = orders.msg_sender();
+                let on_resolved = on_resolved.clone();
+                let callback_key = cache_key.clone();
+                let callback: ThumbnailCallback = Box::new(move |url: &str| {
+                    msg_sender(Some(on_resolved(callback_key, url.to_owned())))
+                });
+                LOOKUPS.with(|lookups| {
+                    let mut lookups = lookups.borrow_mut();
+                    if !lookups.in_flight.contains_key(&cache_key) {
+                        lookups.queued.push_back((
+                            cache_key.clone(),
+                            song.clone(),
+                            providers.clone(),
+                        ));
+                    }
+                    lookups
+                        .in_flight
+                        .entry(cache_key)
+                        .or_default()
+                        .push(callback);
+                });
+            }
+        }
+    }
+    start_lookup_workers();
+}
+
+fn start_lookup_workers() {
+    let to_start = LOOKUPS.with(|lookups| {
+        let mut lookups = lookups.borrow_mut();
+        let idle = MAX_CONCURRENT_LOOKUPS.saturating_sub(lookups.workers);
+        let to_start = idle.min(lookups.queued.len());
+        lookups.workers += to_start;
+        to_start
+    });
+    for _ in 0..to_start {
+        spawn_local(run_lookups());
+    }
+}
+
+/// Resolves queued albums one after another until the queue is empty.
+async fn run_lookups() {
+    loop {
+        let next = LOOKUPS.with(|lookups| {
+            let mut lookups = lookups.borrow_mut();
+            let next = lookups.queued.pop_front();
+            if next.is_none() {
+                lookups.workers -= 1;
+            }
+            next
+        });
+        let (cache_key, song, providers) = match next {
+            Some(next) => next,
+            None => return,
+        };
+        let url = resolve(&song, &providers).await;
+        // callbacks dispatch messages, which may request more thumbnails
+        let callbacks = LOOKUPS.with(|lookups| {
+            lookups
+                .borrow_mut()
+                .in_flight
+                .remove(&cache_key)
+                .unwrap_or_default()
+        });
+        if let Some(url) = url {
+            for callback in callbacks {
+                callback(&url);
+            }
+        }
+    }
+}
+
+pub fn view_thumbnail<Ms>(thumbnails: &HashMap<String, String>, song: &Song) -> Node<Ms> {
+    let thumbnail = cache::key(song.artist.as_deref(), song.album.as_deref())
+        .and_then(|cache_key| thumbnails.get(&cache_key));
+    div![
+        C!["list-item-image"],
+        figure![
+            C!["image", "is-48x48"],
+            img![attrs! {At::Src => thumbnail.map_or("/no_album.png", String::as_str)}],
+        ],
+    ]
+}
@@ -0,0 +1,118 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use seed::{prelude::*, *};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen_futures::spawn_local;
+
+const ALBUM_ART_CACHE_STORAGE_KEY: &str = "dplayer-album-art-cache";
+const FOUND_TTL_MS: f64 = 30.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+// missing covers are retried sooner, someone may have added a cover.jpg in the meantime
+const MISSING_TTL_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+const PERSIST_DELAY_MS: u32 = 2_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CachedArt {
+    Found(String),
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    url: Option<String>,
+    expires_at: f64,
+    // providers that were asked, a miss doesn't hold for providers enabled later
+    #[serde(default)]
+    providers: Vec<String>,
+}
+
+thread_local! {
+    // loaded from `localStorage` on first use
+    static CACHE: RefCell<Option<HashMap<String, CacheEntry>>> = RefCell::new(None);
+    static PERSIST_SCHEDULED: Cell<bool> = Cell::new(false);
+}
+
+/// Cache key for an album, `None` when the song doesn't carry enough tags to identify it.
+pub fn key(artist: Option<&str>, album: Option<&str>) -> Option<String> {
+    match (artist, album) {
+        (Some(artist), Some(album)) if !artist.is_empty() && !album.is_empty() => Some(format!(
+            "{}\u{1f}{}",
+            artist.to_lowercase(),
+            album.to_lowercase()
+        )),
+        _ => None,
+    }
+}
+
+/// Cached art for `key`, a miss only counts if it covered every one of `providers`.
+pub fn get(key: &str, providers: &[&str]) -> Option<CachedArt> {
+    with_cache(|cache| {
+        let entry = cache.get(key)?;
+        if entry.expires_at < js_sys::Date::now() {
+            return None;
+        }
+        let tried_all = providers
+            .iter()
+            .all(|provider| entry.providers.iter().any(|tried| tried == provider));
+        if entry.url.is_none() && !tried_all {
+            return None;
+        }
+        Some(
+            entry
+                .url
+                .clone()
+                .map_or(CachedArt::Missing, CachedArt::Found),
+        )
+    })
+}
+
+pub fn insert(key: String, url: Option<String>, providers: &[&str]) {
+    let ttl = if url.is_some() {
+        FOUND_TTL_MS
+    } else {
+        MISSING_TTL_MS
+    };
+    with_cache(|cache| {
+        let now = js_sys::Date::now();
+        cache.retain(|_, entry| entry.expires_at >= now);
+        cache.insert(
+            key,
+            CacheEntry {
+                url,
+                expires_at: now + ttl,
+                providers: providers
+                    .iter()
+                    .map(|provider| (*provider).to_string())
+                    .collect(),
+            },
+        );
+    });
+    schedule_persist();
+}
+
+/// Writes the cache to `localStorage` once per burst of inserts instead of on every insert.
+fn schedule_persist() {
+    let already_scheduled = PERSIST_SCHEDULED.with(|scheduled| scheduled.replace(true));
+    if already_scheduled {
+        return;
+    }
+    spawn_local(async {
+        cmds::timeout(PERSIST_DELAY_MS, || ()).await;
+        PERSIST_SCHEDULED.with(|scheduled| scheduled.set(false));
+        with_cache(|cache| {
+            if let Err(e) = LocalStorage::insert(ALBUM_ART_CACHE_STORAGE_KEY, &*cache) {
+                log!("Failed to persist album art cache", e);
+            }
+        });
+    });
+}
+
+fn with_cache<T>(f: impl FnOnce(&mut HashMap<String, CacheEntry>) -> T) -> T {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let cache = cache.get_or_insert_with(|| {
+            LocalStorage::get(ALBUM_ART_CACHE_STORAGE_KEY).unwrap_or_default()
+        });
+        f(cache)
+    })
+}
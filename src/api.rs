@@ -0,0 +1,211 @@
+//! Typed client for the dplayer backend REST api.
+//!
+//! Every endpoint has its own function returning [`Result`], so pages never deal with
+//! raw fetch errors and a backend that is briefly down can't panic the app.
+use std::cell::RefCell;
+use std::fmt;
+
+use api_models::{
+    player::Song, playlist::Playlist, settings::Settings, spotify::SpotifyAccountInfo,
+    state::LastState,
+};
+use seed::{prelude::*, *};
+use serde::{de::DeserializeOwned, Deserialize};
+
+pub const DEFAULT_TIMEOUT_MS: u32 = 10_000;
+
+const API_SETTINGS_PATH: &str = "/api/settings";
+const API_STATUS_PATH: &str = "/api/status";
+const API_QUEUE_PATH: &str = "/api/queue";
+const API_PLAYLIST_PATH: &str = "/api/playlist";
+const API_ART_PATH: &str = "/api/art";
+const API_SPOTIFY_IS_AUTH_PATH: &str = "/api/spotify/is-authorized";
+const API_SPOTIFY_GET_AUTH_URL_PATH: &str = "/api/spotify/get-url";
+const API_SPOTIFY_GET_ACCOUNT_INFO_PATH: &str = "/api/spotify/me";
+
+thread_local! {
+    static CONFIG: RefCell<Config> = RefCell::new(Config::default());
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Prepended to every api path, empty means the origin the ui was served from.
+    pub base_url: String,
+    pub timeout_ms: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            base_url: String::new(),
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+        }
+    }
+}
+
+/// Sets the backend location and request timeout used by all api calls.
+pub fn configure(config: Config) {
+    CONFIG.with(|current| *current.borrow_mut() = config);
+}
+
+pub type Result<T> = std::result::Result<T, ApiError>;
+
+#[derive(Debug, Clone)]
+pub enum ApiError {
+    /// The backend could not be reached.
+    Network(String),
+    /// The backend did not respond within the configured timeout.
+    Timeout,
+    /// The backend responded with a non 2xx status.
+    Status { code: u16, text: String },
+    /// The response body did not match the expected type.
+    Decode(String),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(error) => write!(f, "backend unreachable ({})", error),
+            Self::Timeout => write!(f, "backend did not respond in time"),
+            Self::Status { code, text } => write!(f, "backend responded with {} {}", code, text),
+            Self::Decode(error) => write!(f, "unexpected response ({})", error),
+        }
+    }
+}
+
+impl From<FetchError> for ApiError {
+    fn from(error: FetchError) -> Self {
+        match error {
+            FetchError::StatusError(status) => Self::Status {
+                code: status.code,
+                text: status.text,
+            },
+            FetchError::SerdeError(error) => Self::Decode(error.to_string()),
+            // requests are only aborted by their timeout
+            FetchError::NetworkError(error) if is_abort(&error) => Self::Timeout,
+            FetchError::DomException(error) if error.name() == "AbortError" => Self::Timeout,
+            FetchError::DomException(error) => Self::Network(error.message()),
+            FetchError::NetworkError(error)
+            | FetchError::PromiseError(error)
+            | FetchError::RequestError(error) => Self::Network(
+                error
+                    .as_string()
+                    .or_else(|| {
+                        error
+                            .dyn_ref::<js_sys::Error>()
+                            .map(|error| String::from(error.message()))
+                    })
+                    .unwrap_or_else(|| format!("{:?}", error)),
+            ),
+        }
+    }
+}
+
+fn is_abort(error: &JsValue) -> bool {
+    error
+        .dyn_ref::<web_sys::DomException>()
+        .map_or(false, |error| error.name() == "AbortError")
+}
+
+// ------ ------
+//   Endpoints
+// ------ ------
+
+pub async fn get_settings() -> Result<Settings> {
+    get_json(API_SETTINGS_PATH.to_string()).await
+}
+
+pub async fn save_settings(settings: &Settings) -> Result<Settings> {
+    let response = request(API_SETTINGS_PATH.to_string())
+        .method(Method::Post)
+        .json(settings)?
+        .fetch()
+        .await?
+        .check_status()?;
+    Ok(response.json::<Settings>().await?)
+}
+
+pub async fn get_current_status() -> Result<LastState> {
+    get_json(API_STATUS_PATH.to_string()).await
+}
+
+pub async fn get_queue() -> Result<Vec<Song>> {
+    get_json(API_QUEUE_PATH.to_string()).await
+}
+
+pub async fn get_playlists() -> Result<Vec<Playlist>> {
+    get_json(API_PLAYLIST_PATH.to_string()).await
+}
+
+pub async fn get_playlist_items(playlist_id: &str) -> Result<Vec<Song>> {
+    get_json(format!("{}/{}", API_PLAYLIST_PATH, playlist_id)).await
+}
+
+pub async fn spotify_is_authorized() -> Result<bool> {
+    Ok(get_text(API_SPOTIFY_IS_AUTH_PATH.to_string()).await? == "true")
+}
+
+pub async fn get_spotify_auth_url() -> Result<String> {
+    get_text(API_SPOTIFY_GET_AUTH_URL_PATH.to_string()).await
+}
+
+pub async fn get_spotify_account_info() -> Result<SpotifyAccountInfo> {
+    get_json(API_SPOTIFY_GET_ACCOUNT_INFO_PATH.to_string()).await
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtResponse {
+    url: String,
+}
+
+/// Asks the backend for album art of `track` from a single provider,
+/// `None` when the provider has nothing for it.
+pub async fn get_album_art(provider: &str, track: &Song) -> Result<Option<String>> {
+    let mut query = vec![("provider", provider), ("file", track.file.as_str())];
+    if let Some(artist) = track.artist.as_deref() {
+        query.push(("artist", artist));
+    }
+    if let Some(album) = track.album.as_deref() {
+        query.push(("album", album));
+    }
+    let query = query
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, js_sys::encode_uri_component(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+    let response = request(format!("{}?{}", API_ART_PATH, query))
+        .method(Method::Get)
+        .fetch()
+        .await?;
+    // not found by this provider
+    if response.status().code == 404 {
+        return Ok(None);
+    }
+    let art = response.check_status()?.json::<ArtResponse>().await?;
+    Ok(Some(art.url).filter(|url| !url.is_empty()))
+}
+
+// ------ ------
+//    Helpers
+// ------ ------
+
+fn request(path: String) -> Request<'static> {
+    let config = CONFIG.with(|config| config.borrow().clone());
+    Request::new(format!("{}{}", config.base_url, path)).timeout(config.timeout_ms)
+}
+
+async fn get(path: String) -> Result<Response> {
+    Ok(request(path)
+        .method(Method::Get)
+        .fetch()
+        .await?
+        .check_status()?)
+}
+
+async fn get_json<T: DeserializeOwned + 'static>(path: String) -> Result<T> {
+    Ok(get(path).await?.json::<T>().await?)
+}
+
+async fn get_text(path: String) -> Result<String> {
+    Ok(get(path).await?.text().await?)
+}
@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+use std::mem::{discriminant, Discriminant};
+
+use api_models::common::Command;
+use seed::prelude::*;
+
+/// Commands that could not be delivered within this time are dropped and reported.
+pub const COMMAND_TIMEOUT_MS: u32 = 10_000;
+
+#[derive(Debug)]
+struct PendingCommand {
+    kind: Discriminant<Command>,
+    name: String,
+    payload: String,
+    queued_at: f64,
+}
+
+/// A command dropped by `CommandQueue::expire` before the socket accepted it.
+#[derive(Debug, Clone)]
+pub struct ExpiredCommand {
+    pub name: String,
+    kind: Discriminant<Command>,
+}
+
+impl ExpiredCommand {
+    /// Whether the expired command is of the same variant as `command`.
+    pub fn is_kind_of(&self, command: &Command) -> bool {
+        self.kind == discriminant(command)
+    }
+}
+
+/// Broadcast with `orders.notify` so pages can drop state that waited for the commands.
+#[derive(Debug, Clone)]
+pub struct CommandsExpired(pub Vec<ExpiredCommand>);
+
+/// Outbound commands waiting for an open WebSocket.
+///
+/// A command counts as acknowledged once the socket accepts it, until then it stays
+/// queued and is retried on the next flush.
+#[derive(Debug, Default)]
+pub struct CommandQueue {
+    pending: VecDeque<PendingCommand>,
+}
+
+impl CommandQueue {
+    pub fn push(&mut self, command: &Command) -> Result<(), serde_json::Error> {
+        let payload = serde_json::to_string(command)?;
+        let kind = discriminant(command);
+        if is_idempotent(command) {
+            // only the latest value matters, e.g. for volume changes while dragging
+            self.pending.retain(|pending| pending.kind != kind);
+        }
+        self.pending.push_back(PendingCommand {
+            kind,
+            name: format!("{:?}", command),
+            payload,
+            queued_at: js_sys::Date::now(),
+        });
+        Ok(())
+    }
+
+    /// Sends queued commands in order, stopping at the first one the socket doesn't accept.
+    pub fn flush(&mut self, web_socket: &WebSocket) {
+        if web_socket.state() != web_socket::State::Open {
+            return;
+        }
+        while let Some(pending) = self.pending.front() {
+            if let Err(e) = web_socket.send_text(&pending.payload) {
+                log!("Failed to send command", pending.name, e);
+                break;
+            }
+            self.pending.pop_front();
+        }
+    }
+
+    /// Drops commands queued for longer than `COMMAND_TIMEOUT_MS` and returns them.
+    pub fn expire(&mut self) -> Vec<ExpiredCommand> {
+        let now = js_sys::Date::now();
+        let (expired, pending): (Vec<_>, VecDeque<_>) = self
+            .pending
+            .drain(..)
+            .partition(|pending| now - pending.queued_at >= f64::from(COMMAND_TIMEOUT_MS));
+        self.pending = pending;
+        expired
+            .into_iter()
+            .map(|pending| ExpiredCommand {
+                name: pending.name,
+                kind: pending.kind,
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+const fn is_idempotent(command: &Command) -> bool {
+    matches!(command, Command::SetVol(_) | Command::SwitchToPlayer(_))
+}
@@ -0,0 +1,74 @@
+use seed::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shortcut {
+    TogglePlay,
+    SeekBackward,
+    SeekForward,
+    VolumeUp,
+    VolumeDown,
+    Next,
+    Prev,
+    Shuffle,
+    Player,
+    Queue,
+    Playlist,
+    Settings,
+    ToggleHelp,
+    CloseHelp,
+}
+
+/// Key and description of every shortcut, shown in the help overlay.
+pub const SHORTCUTS_HELP: [(&str, &str); 12] = [
+    ("Space", "Play / pause"),
+    ("← / →", "Seek backward / forward"),
+    ("↑ / ↓", "Volume up / down"),
+    ("N", "Next song"),
+    ("P", "Previous song"),
+    ("S", "Toggle shuffle"),
+    ("1", "Player"),
+    ("2", "Queue"),
+    ("3", "Playlists"),
+    ("4", "Settings"),
+    ("?", "Show / hide this help"),
+    ("Esc", "Close this help"),
+];
+
+/// Maps a `keydown` event to a shortcut, preventing the browser default for matched keys.
+///
+/// Keys are ignored while typing into form fields and when combined with modifiers,
+/// so browser and OS shortcuts keep working.
+pub fn shortcut_for(event: &web_sys::KeyboardEvent) -> Option<Shortcut> {
+    if event.ctrl_key() || event.meta_key() || event.alt_key() || is_typing(event) {
+        return None;
+    }
+    let shortcut = match event.key().as_str() {
+        " " => Shortcut::TogglePlay,
+        "ArrowLeft" => Shortcut::SeekBackward,
+        "ArrowRight" => Shortcut::SeekForward,
+        "ArrowUp" => Shortcut::VolumeUp,
+        "ArrowDown" => Shortcut::VolumeDown,
+        "n" | "N" => Shortcut::Next,
+        "p" | "P" => Shortcut::Prev,
+        "s" | "S" => Shortcut::Shuffle,
+        "1" => Shortcut::Player,
+        "2" => Shortcut::Queue,
+        "3" => Shortcut::Playlist,
+        "4" => Shortcut::Settings,
+        "?" => Shortcut::ToggleHelp,
+        "Escape" => Shortcut::CloseHelp,
+        _ => return None,
+    };
+    event.prevent_default();
+    Some(shortcut)
+}
+
+fn is_typing(event: &web_sys::KeyboardEvent) -> bool {
+    event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+        .map_or(false, |element| {
+            element.is_content_editable()
+                || matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        })
+}
@@ -1,17 +1,43 @@
+use std::convert::TryFrom;
 use std::rc::Rc;
 
-use api_models::state::StateChangeEvent;
+use api_models::common::Command;
+use api_models::player::Song;
+use api_models::state::{LastState, StateChangeEvent};
+use command_queue::{CommandQueue, CommandsExpired, COMMAND_TIMEOUT_MS};
+use keyboard::Shortcut;
 use page::settings;
+use protocol::DecodeError;
 use seed::{prelude::*, *};
+use store::PlayerStore;
 use strum_macros::IntoStaticStr;
+use toast::{Toast, Toasts};
+use ui_settings::UiSettings;
+use wasm_bindgen_futures::spawn_local;
+mod album_art;
+mod api;
+mod command_queue;
+mod keyboard;
+mod media_session;
 mod page;
+mod protocol;
+mod store;
+mod toast;
+mod ui_settings;
 
-// todo: read host from current location
-#[cfg(feature = "remote")]
-const WS_URL: &str = "ws://dplayer.lan:8000/api/ws";
-
-#[cfg(feature = "local")]
-const WS_URL: &str = "ws://localhost:8000/api/ws";
+const API_URL_QUERY_PARAM: &str = "api";
+const API_URL_STORAGE_KEY: &str = "dplayer-api-url";
+const WS_PATH: &str = "/api/ws";
+const WS_URL_QUERY_PARAM: &str = "ws";
+const WS_URL_STORAGE_KEY: &str = "dplayer-ws-url";
+const WS_MAX_RECONNECT_ATTEMPTS: usize = 10;
+const WS_HEARTBEAT_INTERVAL_MS: u32 = 10_000;
+// without any inbound frame for this long the connection is considered dead
+const WS_STALE_AFTER_MS: f64 = 30_000.0;
+const WS_PONG_TIMEOUT_MS: u32 = 3_000;
+// the elapsed time is shown in whole seconds, re-rendering more often changes nothing
+const PROGRESS_TICK_MS: u32 = 1_000;
+const DEBUG_QUERY_PARAM: &str = "debug";
 const SETTINGS: &str = "settings";
 const PLAYLIST: &str = "playlist";
 const QUEUE: &str = "queue";
@@ -26,8 +52,40 @@ const PLAYER: &str = "player";
 struct Model {
     base_url: Url,
     page: Page,
-    web_socket: WebSocket,
+    player_store: PlayerStore,
+    ui_settings: UiSettings,
+    progress_ticker: Option<StreamHandle>,
+    album_art_lookup: Option<CmdHandle>,
+    ws_url: String,
+    // `None` when the socket could not even be created, e.g. for a malformed url
+    web_socket: Option<WebSocket>,
     web_socket_reconnector: Option<StreamHandle>,
+    connection_state: ConnectionState,
+    last_ws_activity: f64,
+    // whether the backend answered a ping on this connection, the watchdog is off until then
+    backend_answers_ping: bool,
+    command_queue: CommandQueue,
+    toasts: Toasts,
+    show_shortcuts: bool,
+    debug: bool,
+    rejected_frames: usize,
+    last_rejected_frame: Option<DecodeError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionState {
+    Connecting,
+    Open,
+    Reconnecting(usize),
+    Failed,
+    /// Closed by the UI itself, no reconnect until the user asks for it.
+    Closed,
+}
+
+impl ConnectionState {
+    pub const fn is_open(self) -> bool {
+        matches!(self, Self::Open)
+    }
 }
 
 pub enum Msg {
@@ -36,8 +94,23 @@ pub enum Msg {
     WebSocketClosed(CloseEvent),
     WebSocketFailed,
     ReconnectWebSocket(usize),
+    RetryWebSocket,
+    WebSocketPong,
+    Heartbeat,
+    PongTimeout(f64),
+    VisibilityChanged,
     UrlChanged(subs::UrlChanged),
     StatusChangeEventReceived(StateChangeEvent),
+    CurrentStatusFetched(api::Result<LastState>),
+    WebSocketFrameRejected(DecodeError),
+    ProgressTick,
+    AlbumArtResolved { file: String, image: Option<String> },
+    ExpireCommands,
+    ShowToast(Toast),
+    UiSettingsChanged(UiSettings),
+    DismissToast(u64),
+    SendCommand(Command),
+    Shortcut(Shortcut),
     Settings(page::settings::Msg),
     Player(page::player::Msg),
     Playlist(page::playlist::Msg),
@@ -71,6 +144,32 @@ impl Page {
             _ => Self::NotFound,
         }
     }
+
+    /// Re-fetches the state the current page was initialised with, so it doesn't keep
+    /// showing data from before a connection outage.
+    fn resync(&self, orders: &mut impl Orders<Msg>) {
+        match self {
+            Self::Queue(_) => {
+                orders.send_msg(Msg::Queue(page::queue::Msg::RefreshQueue));
+            }
+            Self::Playlist(model) => {
+                let mut orders = orders.proxy(Msg::Playlist);
+                orders.perform_cmd(async {
+                    page::playlist::Msg::PlaylistsFetched(api::get_playlists().await)
+                });
+                if let Some(pl_id) = model.selected_playlist_id.clone() {
+                    orders.perform_cmd(async move {
+                        page::playlist::Msg::PlaylistItemsFetched(
+                            api::get_playlist_items(&pl_id).await,
+                        )
+                    });
+                }
+            }
+            // player state lives in the shared `PlayerStore`, settings are edited locally
+            // and only replaced on an explicit save
+            Self::Home | Self::Player(_) | Self::Settings(_) | Self::NotFound => {}
+        }
+    }
 }
 
 // ------ ------
@@ -102,14 +201,50 @@ impl<'a> Urls<'a> {
 // ------ ------
 
 fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
+    let api_url =
+        persisted_query_param(&url, API_URL_QUERY_PARAM, API_URL_STORAGE_KEY, is_http_url);
+    api::configure(api::Config {
+        base_url: api_url.unwrap_or_default(),
+        ..api::Config::default()
+    });
     orders
         .subscribe(Msg::UrlChanged)
-        .notify(subs::UrlChanged(url.clone()));
+        .subscribe(Msg::ShowToast)
+        .subscribe(Msg::UiSettingsChanged)
+        .notify(subs::UrlChanged(url.clone()))
+        .stream(streams::interval(WS_HEARTBEAT_INTERVAL_MS, || {
+            Msg::Heartbeat
+        }))
+        .stream(streams::document_event(Ev::VisibilityChange, |_| {
+            Msg::VisibilityChanged
+        }))
+        .stream(streams::window_event(Ev::KeyDown, |event| {
+            keyboard::shortcut_for(event.unchecked_ref()).map(Msg::Shortcut)
+        }));
+    orders.perform_cmd(async { Msg::CurrentStatusFetched(api::get_current_status().await) });
+    let msg_sender = orders.msg_sender();
+    media_session::set_action_handlers(move |cmd| msg_sender(Some(Msg::SendCommand(cmd))));
+    let ws_url = get_ws_url(&url);
+    let debug = url.search().contains_key(DEBUG_QUERY_PARAM);
     Model {
         base_url: url.to_base_url(),
         page: Page::init(url, orders),
-        web_socket: create_websocket(orders),
+        player_store: PlayerStore::default(),
+        ui_settings: ui_settings::load(),
+        progress_ticker: None,
+        album_art_lookup: None,
+        web_socket: create_websocket(&ws_url, orders),
+        ws_url,
         web_socket_reconnector: None,
+        connection_state: ConnectionState::Connecting,
+        last_ws_activity: js_sys::Date::now(),
+        backend_answers_ping: false,
+        command_queue: CommandQueue::default(),
+        toasts: Toasts::default(),
+        show_shortcuts: false,
+        debug,
+        rejected_frames: 0,
+        last_rejected_frame: None,
     }
 }
 
@@ -121,15 +256,32 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     match msg {
         Msg::WebSocketOpened => {
             model.web_socket_reconnector = None;
+            let reconnected = model.connection_state != ConnectionState::Connecting;
+            model.connection_state = ConnectionState::Open;
+            model.last_ws_activity = js_sys::Date::now();
+            model.backend_answers_ping = false;
             log!("WebSocket connection is open now");
+            // probe whether the backend supports keepalive pings
+            send_ping(model);
+            if let Some(web_socket) = model.web_socket.as_ref() {
+                model.command_queue.flush(web_socket);
+            }
+            if reconnected {
+                orders.perform_cmd(async {
+                    Msg::CurrentStatusFetched(api::get_current_status().await)
+                });
+                model.page.resync(orders);
+            }
         }
 
         Msg::CloseWebSocket => {
             model.web_socket_reconnector = None;
-            model
-                .web_socket
-                .close(None, Some("user clicked Close button"))
-                .unwrap();
+            model.connection_state = ConnectionState::Closed;
+            if let Some(web_socket) = model.web_socket.as_ref() {
+                web_socket
+                    .close(None, Some("user clicked Close button"))
+                    .unwrap();
+            }
         }
 
         Msg::WebSocketClosed(close_event) => {
@@ -140,43 +292,182 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             log!("Reason:", close_event.reason());
             log!("==================");
 
-            // Chrome doesn't invoke `on_error` when the connection is lost.
-            if !close_event.was_clean() && model.web_socket_reconnector.is_none() {
-                model.web_socket_reconnector = Some(
-                    orders.stream_with_handle(streams::backoff(None, Msg::ReconnectWebSocket)),
-                );
+            // sockets replaced in the meantime, e.g. by `drop_stale_connection`, don't matter
+            let is_current = model.web_socket.as_ref().map_or(false, |web_socket| {
+                close_event.target().map_or(false, |target| {
+                    js_sys::Object::is(target.as_ref(), web_socket.raw_web_socket().as_ref())
+                })
+            });
+            if !is_current || model.connection_state == ConnectionState::Closed {
+                return;
             }
+            // Clean closes need a reconnect too, e.g. the backend restarts after a settings
+            // change. Chrome doesn't invoke `on_error` when the connection is lost.
+            start_reconnecting(model, orders);
         }
 
         Msg::WebSocketFailed => {
             log!("WebSocket failed");
-            if model.web_socket_reconnector.is_none() {
-                model.web_socket_reconnector = Some(
-                    orders.stream_with_handle(streams::backoff(None, Msg::ReconnectWebSocket)),
-                );
-            }
+            start_reconnecting(model, orders);
         }
 
         Msg::ReconnectWebSocket(retries) => {
             log!("Reconnect attempt:", retries);
-            model.web_socket = create_websocket(orders);
+            if retries > WS_MAX_RECONNECT_ATTEMPTS {
+                model.web_socket_reconnector = None;
+                model.connection_state = ConnectionState::Failed;
+            } else {
+                model.connection_state = ConnectionState::Reconnecting(retries);
+                model.web_socket = create_websocket(&model.ws_url, orders);
+            }
         }
+
+        Msg::RetryWebSocket => {
+            model.connection_state = ConnectionState::Reconnecting(0);
+            model.web_socket = create_websocket(&model.ws_url, orders);
+        }
+        Msg::WebSocketPong => {
+            model.last_ws_activity = js_sys::Date::now();
+            model.backend_answers_ping = true;
+        }
+
+        Msg::Heartbeat => {
+            // an idle socket is only considered dead if the backend is known to answer pings
+            if model.connection_state.is_open() && model.backend_answers_ping {
+                if js_sys::Date::now() - model.last_ws_activity > WS_STALE_AFTER_MS {
+                    drop_stale_connection(model, orders);
+                } else {
+                    send_ping(model);
+                }
+            }
+        }
+
+        Msg::PongTimeout(ping_sent_at) => {
+            if model.connection_state.is_open() && model.last_ws_activity < ping_sent_at {
+                drop_stale_connection(model, orders);
+            }
+        }
+
+        Msg::VisibilityChanged => {
+            if document().hidden() {
+                return;
+            }
+            match model.connection_state {
+                ConnectionState::Open if model.backend_answers_ping => {
+                    // a socket that survived the device sleeping may be half-dead
+                    let ping_sent_at = js_sys::Date::now();
+                    send_ping(model);
+                    orders.perform_cmd(cmds::timeout(WS_PONG_TIMEOUT_MS, move || {
+                        Msg::PongTimeout(ping_sent_at)
+                    }));
+                }
+                ConnectionState::Reconnecting(_) | ConnectionState::Failed => {
+                    // don't wait for the next backoff step
+                    model.web_socket_reconnector = None;
+                    if model.connection_state == ConnectionState::Failed {
+                        model.connection_state = ConnectionState::Reconnecting(0);
+                    }
+                    model.web_socket = create_websocket(&model.ws_url, orders);
+                }
+                ConnectionState::Open | ConnectionState::Connecting | ConnectionState::Closed => {}
+            }
+        }
+
         Msg::UrlChanged(subs::UrlChanged(url)) => model.page = Page::init(url, orders),
 
         Msg::StatusChangeEventReceived(chg_ev) => {
-            if let Page::Player(model) = &mut model.page {
-                page::player::update(
-                    page::player::Msg::StatusChangeEventReceived(chg_ev),
-                    model,
-                    &mut orders.proxy(Msg::Player),
-                );
+            model.last_ws_activity = js_sys::Date::now();
+            publish_event(model, chg_ev, orders);
+        }
+
+        Msg::CurrentStatusFetched(Ok(last_state)) => {
+            for event in store::events_from_last_state(last_state) {
+                publish_event(model, event, orders);
+            }
+        }
+
+        Msg::ProgressTick => model.player_store.tick(),
+
+        Msg::AlbumArtResolved { file, image } => {
+            model.album_art_lookup = None;
+            let image_url = image.clone();
+            if model.player_store.set_album_art(&file, image) {
+                if let Some(image_url) = image_url {
+                    media_session::update_artwork(&image_url);
+                }
+            }
+        }
+
+        Msg::CurrentStatusFetched(Err(e)) => {
+            log!("Failed to fetch player status", e);
+            orders.notify(Toast::error(format!(
+                "Failed to fetch player status: {}",
+                e
+            )));
+        }
+
+        Msg::WebSocketFrameRejected(error) => {
+            model.last_ws_activity = js_sys::Date::now();
+            log!("Ignoring WebSocket frame:", error.to_string());
+            model.rejected_frames += 1;
+            model.last_rejected_frame = Some(error);
+        }
+
+        Msg::ExpireCommands => {
+            let expired = model.command_queue.expire();
+            if !expired.is_empty() {
+                let names = expired
+                    .iter()
+                    .map(|command| command.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                log!("Commands not delivered:", names);
+                if expired
+                    .iter()
+                    .any(|command| command.is_kind_of(&Command::SetVol(0)))
+                {
+                    model.player_store.revert_volume();
+                }
+                orders
+                    .notify(Toast::error(format!(
+                        "Could not deliver to the player: {}",
+                        names
+                    )))
+                    .notify(CommandsExpired(expired));
             }
         }
 
+        Msg::ShowToast(toast) => {
+            let timeout = toast.timeout_ms();
+            let id = model.toasts.push(toast);
+            orders.perform_cmd(cmds::timeout(timeout, move || Msg::DismissToast(id)));
+        }
+
+        Msg::DismissToast(id) => model.toasts.dismiss(id),
+
+        Msg::UiSettingsChanged(ui_settings) => model.ui_settings = ui_settings,
+
+        Msg::SendCommand(cmd) => {
+            model.player_store.apply_command(&cmd);
+            send_command(
+                &mut model.command_queue,
+                model.web_socket.as_ref(),
+                &cmd,
+                orders,
+            );
+        }
+
+        Msg::Shortcut(shortcut) => handle_shortcut(shortcut, model, orders),
+
         Msg::Settings(msg) => {
             if let Page::Settings(sett_model) = &mut model.page {
                 if let settings::Msg::SendCommand(cmd) = &msg {
-                    model.web_socket.send_json(cmd).unwrap();
+                    send_command(
+                        &mut model.command_queue,
+                        model.web_socket.as_ref(),
+                        cmd,
+                        orders,
+                    );
                 }
                 page::settings::update(msg, sett_model, &mut orders.proxy(Msg::Settings));
             }
@@ -186,7 +477,13 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             log!("Lib::Player {}", msg);
             if let Page::Player(player_model) = &mut model.page {
                 if let page::player::Msg::SendCommand(cmd) = &msg {
-                    model.web_socket.send_json(cmd).unwrap();
+                    model.player_store.apply_command(cmd);
+                    send_command(
+                        &mut model.command_queue,
+                        model.web_socket.as_ref(),
+                        cmd,
+                        orders,
+                    );
                 }
                 page::player::update(msg, player_model, &mut orders.proxy(Msg::Player));
             }
@@ -195,7 +492,12 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
         Msg::Playlist(msg) => {
             if let Page::Playlist(player_model) = &mut model.page {
                 if let page::playlist::Msg::SendCommand(cmd) = &msg {
-                    model.web_socket.send_json(cmd).unwrap();
+                    send_command(
+                        &mut model.command_queue,
+                        model.web_socket.as_ref(),
+                        cmd,
+                        orders,
+                    );
                 }
 
                 page::playlist::update(msg, player_model, &mut orders.proxy(Msg::Playlist));
@@ -204,7 +506,12 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
         Msg::Queue(msg) => {
             if let Page::Queue(player_model) = &mut model.page {
                 if let page::queue::Msg::SendCommand(cmd) = &msg {
-                    model.web_socket.send_json(cmd).unwrap();
+                    send_command(
+                        &mut model.command_queue,
+                        model.web_socket.as_ref(),
+                        cmd,
+                        orders,
+                    );
                 }
                 page::queue::update(msg, player_model, &mut orders.proxy(Msg::Queue));
             }
@@ -212,30 +519,358 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     }
 }
 
+fn handle_shortcut(shortcut: Shortcut, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    let command = match shortcut {
+        Shortcut::TogglePlay if model.player_store.is_playing() => Command::Pause,
+        Shortcut::TogglePlay => Command::Play,
+        Shortcut::SeekBackward | Shortcut::SeekForward => {
+            // same interpolated position the progress bars show
+            let (elapsed, total) = match (
+                model.player_store.elapsed_secs(),
+                model.player_store.total_secs(),
+            ) {
+                (Some(elapsed), Some(total)) => (elapsed, total),
+                _ => return,
+            };
+            let step = model.ui_settings.seek_step_secs;
+            let target = if shortcut == Shortcut::SeekBackward {
+                elapsed.saturating_sub(step)
+            } else {
+                (elapsed + step).min(total)
+            };
+            Command::Seek(u16::try_from(target).unwrap_or(u16::MAX))
+        }
+        Shortcut::VolumeUp => Command::VolUp,
+        Shortcut::VolumeDown => Command::VolDown,
+        Shortcut::Next => Command::Next,
+        Shortcut::Prev => Command::Prev,
+        Shortcut::Shuffle => Command::RandomToggle,
+        Shortcut::Player => return Urls::player_abs().go_and_load(),
+        Shortcut::Queue => return Urls::queue_abs().go_and_load(),
+        Shortcut::Playlist => return Urls::playlist_abs().go_and_load(),
+        Shortcut::Settings => return Urls::settings_abs().go_and_load(),
+        Shortcut::ToggleHelp => {
+            model.show_shortcuts = !model.show_shortcuts;
+            return;
+        }
+        Shortcut::CloseHelp => {
+            model.show_shortcuts = false;
+            return;
+        }
+    };
+    orders.send_msg(Msg::SendCommand(command));
+}
+
+fn publish_event(model: &mut Model, event: StateChangeEvent, orders: &mut impl Orders<Msg>) {
+    if let StateChangeEvent::Error(error) = &event {
+        orders.notify(Toast::error(error.clone()));
+    }
+    orders.notify(event.clone());
+    let previous_file = model
+        .player_store
+        .current_track_file()
+        .map(ToOwned::to_owned);
+    let track_changed = matches!(event, StateChangeEvent::CurrentTrackInfoChanged(_));
+    let player_info_changed = matches!(event, StateChangeEvent::PlayerInfoChanged(_));
+    model.player_store.apply(event);
+
+    // published here rather than by the player page, so the OS media controls stay current
+    // on every page
+    if track_changed {
+        let track = model.player_store.current_track_info.clone();
+        media_session::update_metadata(track.as_ref(), model.player_store.album_art.as_deref());
+        if let Some(track) = track {
+            if previous_file.as_deref() != Some(track.file.as_str()) {
+                request_album_art(model, track, orders);
+            }
+        }
+    }
+    if player_info_changed {
+        if let Some(player_info) = model.player_store.player_info.as_ref() {
+            media_session::update_position(player_info);
+        }
+        media_session::set_playing(model.player_store.is_playing());
+    }
+
+    // the elapsed time only has to be re-rendered while it advances
+    if !model.player_store.is_playing() {
+        model.progress_ticker = None;
+    } else if model.progress_ticker.is_none() {
+        model.progress_ticker = Some(
+            orders.stream_with_handle(streams::interval(PROGRESS_TICK_MS, || Msg::ProgressTick)),
+        );
+    }
+}
+
+/// Starts resolving the cover of `track`, cancelling a lookup still running for a previous one.
+fn request_album_art(model: &mut Model, track: Song, orders: &mut impl Orders<Msg>) {
+    model.album_art_lookup = None;
+    // streams come with their own artwork url
+    if track.uri.is_some() {
+        return;
+    }
+    let providers = album_art::providers(&model.ui_settings);
+    model.album_art_lookup = Some(orders.perform_cmd_with_handle(async move {
+        let image = album_art::resolve(&track, &providers).await;
+        Msg::AlbumArtResolved {
+            file: track.file,
+            image,
+        }
+    }));
+}
+
+fn send_command(
+    command_queue: &mut CommandQueue,
+    web_socket: Option<&WebSocket>,
+    cmd: &Command,
+    orders: &mut impl Orders<Msg>,
+) {
+    if let Err(e) = command_queue.push(cmd) {
+        log!("Failed to encode command", cmd, e.to_string());
+        return;
+    }
+    if let Some(web_socket) = web_socket {
+        command_queue.flush(web_socket);
+    }
+    if !command_queue.is_empty() {
+        orders.perform_cmd(cmds::timeout(COMMAND_TIMEOUT_MS, || Msg::ExpireCommands));
+    }
+}
+
+fn send_ping(model: &Model) {
+    if let Some(web_socket) = model.web_socket.as_ref() {
+        let _ = web_socket.send_text(protocol::PING_FRAME);
+    }
+}
+
+fn drop_stale_connection(model: &mut Model, orders: &mut impl Orders<Msg>) {
+    log!("WebSocket connection is stale, reconnecting");
+    if let Some(web_socket) = model.web_socket.as_ref() {
+        let _ = web_socket.close(None, Some("stale connection"));
+    }
+    model.web_socket = None;
+    start_reconnecting(model, orders);
+}
+
+fn start_reconnecting(model: &mut Model, orders: &mut impl Orders<Msg>) {
+    // Both `on_error` and `on_close` may fire for the same failure, and after giving up
+    // only an explicit retry should start a new round of attempts.
+    if model.web_socket_reconnector.is_some() || model.connection_state == ConnectionState::Failed {
+        return;
+    }
+    model.connection_state = ConnectionState::Reconnecting(0);
+    model.web_socket_reconnector =
+        Some(orders.stream_with_handle(streams::backoff(None, Msg::ReconnectWebSocket)));
+}
+
 // ------ ------
 //     View
 // ------ ------
 fn view(model: &Model) -> impl IntoNodes<Msg> {
     div![
         C!["container"],
-        view_navigation_tabs(&model.page),
-        view_content(&model.page, &model.base_url),
+        view_navigation_tabs(&model.page, model.connection_state),
+        view_mini_player(model),
+        view_connection_banner(model.connection_state),
+        view_content(model),
+        IF!(model.debug => view_debug_panel(model)),
+        view_shortcuts_help(model.show_shortcuts),
+        toast::view(&model.toasts, Msg::DismissToast),
+    ]
+}
+
+fn view_shortcuts_help(active: bool) -> Node<Msg> {
+    div![
+        C!["modal", IF!(active => "is-active")],
+        div![
+            C!["modal-background"],
+            ev(Ev::Click, |_| Msg::Shortcut(Shortcut::CloseHelp))
+        ],
+        div![
+            C!["modal-content"],
+            div![
+                C!["box"],
+                h2![C!["title", "is-5"], "Keyboard shortcuts"],
+                table![
+                    C!["table", "is-fullwidth", "is-narrow"],
+                    tbody![keyboard::SHORTCUTS_HELP
+                        .iter()
+                        .map(|&(key, description)| { tr![td![kbd![key]], td![description]] })],
+                ],
+            ],
+        ],
+        button![
+            C!["modal-close", "is-large"],
+            attrs! {"aria-label" => "close"},
+            ev(Ev::Click, |_| Msg::Shortcut(Shortcut::CloseHelp))
+        ],
+    ]
+}
+
+fn view_debug_panel(model: &Model) -> Node<Msg> {
+    div![
+        C!["notification", "is-family-monospace", "is-size-7"],
+        p![format!(
+            "WebSocket: {} ({:?})",
+            model.ws_url, model.connection_state
+        )],
+        p![format!(
+            "Protocol version: {}, rejected frames: {}",
+            protocol::PROTOCOL_VERSION,
+            model.rejected_frames
+        )],
+        model
+            .last_rejected_frame
+            .as_ref()
+            .map(|error| p![format!("Last rejected: {}", error)]),
     ]
 }
 
 // ----- view_content ------
 
-fn view_content(page: &Page, base_url: &Url) -> Node<Msg> {
-    match page {
-        Page::Home => page::home::view(base_url),
+fn view_content(model: &Model) -> Node<Msg> {
+    let player_store = &model.player_store;
+    match &model.page {
+        Page::Home => page::home::view(&model.base_url),
         Page::NotFound => page::not_found::view(),
         Page::Settings(model) => page::settings::view(model).map_msg(Msg::Settings),
-        Page::Player(model) => page::player::view(model).map_msg(Msg::Player),
+        Page::Player(page_model) => {
+            page::player::view(page_model, player_store, model.connection_state.is_open())
+                .map_msg(Msg::Player)
+        }
         Page::Playlist(model) => page::playlist::view(model).map_msg(Msg::Playlist),
-        Page::Queue(model) => page::queue::view(model).map_msg(Msg::Queue),
+        Page::Queue(model) => page::queue::view(model, player_store).map_msg(Msg::Queue),
     }
 }
-fn view_navigation_tabs(page: &Page) -> Node<Msg> {
+fn view_connection_banner(connection_state: ConnectionState) -> Node<Msg> {
+    match connection_state {
+        ConnectionState::Open | ConnectionState::Connecting => empty!(),
+        ConnectionState::Reconnecting(attempt) => div![
+            C![
+                "notification",
+                "is-warning",
+                "is-light",
+                "mb-0",
+                "has-text-centered"
+            ],
+            if attempt == 0 {
+                "Connection to the player lost, reconnecting...".to_string()
+            } else {
+                format!(
+                    "Connection to the player lost, reconnecting (attempt {})...",
+                    attempt
+                )
+            }
+        ],
+        ConnectionState::Failed => div![
+            C![
+                "notification",
+                "is-danger",
+                "is-light",
+                "mb-0",
+                "has-text-centered"
+            ],
+            "Unable to connect to the player. ",
+            a!["Try again", ev(Ev::Click, |_| Msg::RetryWebSocket)],
+        ],
+        ConnectionState::Closed => div![
+            C!["notification", "is-light", "mb-0", "has-text-centered"],
+            "Disconnected from the player. ",
+            a!["Connect", ev(Ev::Click, |_| Msg::RetryWebSocket)],
+        ],
+    }
+}
+
+fn view_mini_player(model: &Model) -> Node<Msg> {
+    let track = match (&model.page, model.player_store.current_track_info.as_ref()) {
+        (Page::Player(_), _) | (_, None) => return empty!(),
+        (_, Some(track)) => track,
+    };
+    let playing = model.player_store.is_playing();
+    let disabled = !model.connection_state.is_open();
+    div![
+        C!["mini-player"],
+        model.player_store.elapsed_secs().map(|elapsed| progress![
+            C!["progress", "is-small", "is-success"],
+            attrs! {"value"=> elapsed},
+            attrs! {"max"=> model.player_store.total_secs().unwrap_or_default()},
+        ]),
+        nav![
+            C!["level", "is-mobile"],
+            div![
+                C!["level-left", "mini-player-info"],
+                div![
+                    p![C!["has-text-weight-semibold"], track.get_title()],
+                    IF!(track.artist.is_some() =>
+                        p![C!["is-size-7"], track.artist.as_ref().map_or("NA", |f| f)]
+                    ),
+                ],
+            ],
+            div![
+                C!["level-right"],
+                div![
+                    C!["buttons"],
+                    if playing {
+                        button![
+                            IF!(disabled => attrs!{"disabled"=>true}),
+                            C!["button", "is-small"],
+                            span![C!("icon"), i![C!("fas fa-pause")]],
+                            ev(Ev::Click, |_| Msg::SendCommand(Command::Pause))
+                        ]
+                    } else {
+                        button![
+                            IF!(disabled => attrs!{"disabled"=>true}),
+                            C!["button", "is-small"],
+                            span![C!("icon"), i![C!("fas fa-play")]],
+                            ev(Ev::Click, |_| Msg::SendCommand(Command::Play))
+                        ]
+                    },
+                    button![
+                        IF!(disabled => attrs!{"disabled"=>true}),
+                        C!["button", "is-small"],
+                        span![C!("icon"), i![C!("fas fa-step-forward")]],
+                        ev(Ev::Click, |_| Msg::SendCommand(Command::Next))
+                    ],
+                ],
+            ],
+        ],
+    ]
+}
+
+fn view_connection_badge(connection_state: ConnectionState) -> Node<Msg> {
+    let (icon, color, title) = match connection_state {
+        ConnectionState::Connecting => ("sync", "has-text-grey", "Connecting".to_string()),
+        ConnectionState::Open => ("wifi", "has-text-success", "Connected".to_string()),
+        ConnectionState::Reconnecting(attempt) => (
+            "sync_problem",
+            "has-text-warning",
+            format!("Reconnecting (attempt {})", attempt),
+        ),
+        ConnectionState::Failed => (
+            "signal_wifi_off",
+            "has-text-danger",
+            "Disconnected".to_string(),
+        ),
+        ConnectionState::Closed => ("wifi_off", "has-text-grey", "Disconnected".to_string()),
+    };
+    let can_retry = matches!(
+        connection_state,
+        ConnectionState::Failed | ConnectionState::Closed
+    );
+    li![
+        C!["connection-badge"],
+        a![
+            attrs! {At::Title => title},
+            span![
+                C!["icon", "is-small", color],
+                i![C!["material-icons"], attrs!("aria-hidden" => "true"), icon],
+            ],
+        ],
+        IF!(can_retry => ev(Ev::Click, |_| Msg::RetryWebSocket)),
+    ]
+}
+
+fn view_navigation_tabs(page: &Page, connection_state: ConnectionState) -> Node<Msg> {
     let page_name: &str = page.into();
     div![
         C!["tabs", "is-toggle", "is-centered", "is-fullwidth"],
@@ -272,6 +907,7 @@ fn view_navigation_tabs(page: &Page) -> Node<Msg> {
                 ]],
                 ev(Ev::Click, |_| { Urls::settings_abs().go_and_load() }),
             ],
+            view_connection_badge(connection_state),
         ]
     ]
 }
@@ -310,24 +946,113 @@ pub fn start() {
     App::start("app", init, update, view);
 }
 
-fn create_websocket(orders: &impl Orders<Msg>) -> WebSocket {
+/// Returns the value of query parameter `param`, remembering it under `storage_key`
+/// for later visits without it. An empty value forgets the stored one.
+fn persisted_query_param(
+    url: &Url,
+    param: &str,
+    storage_key: &str,
+    is_valid: impl Fn(&str) -> bool,
+) -> Option<String> {
+    if let Some(value) = url.search().get(param).and_then(|values| values.first()) {
+        if value.is_empty() {
+            let _ = LocalStorage::remove(storage_key);
+        } else if is_valid(value) {
+            let _ = LocalStorage::insert(storage_key, value);
+            return Some(value.clone());
+        } else {
+            log!("Ignoring invalid query parameter", param, value);
+        }
+    }
+    let stored: Result<String, _> = LocalStorage::get(storage_key);
+    match stored {
+        Ok(value) if is_valid(&value) => Some(value),
+        Ok(_) => {
+            // saved before values were validated
+            let _ = LocalStorage::remove(storage_key);
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+fn is_ws_url(value: &str) -> bool {
+    web_sys::Url::new(value).map_or(false, |url| {
+        matches!(url.protocol().as_str(), "ws:" | "wss:")
+    })
+}
+
+fn is_http_url(value: &str) -> bool {
+    web_sys::Url::new(value).map_or(false, |url| {
+        matches!(url.protocol().as_str(), "http:" | "https:")
+    })
+}
+
+/// Resolves the backend WebSocket endpoint.
+///
+/// An explicit `?ws=<url>` query parameter wins and is persisted for later visits
+/// (an empty `?ws=` clears it), then the persisted value is used, and finally the
+/// endpoint is derived from the location the UI was served from.
+fn get_ws_url(url: &Url) -> String {
+    if let Some(ws_url) =
+        persisted_query_param(url, WS_URL_QUERY_PARAM, WS_URL_STORAGE_KEY, is_ws_url)
+    {
+        return ws_url;
+    }
+    let location = window().location();
+    let scheme = match location.protocol().as_deref() {
+        Ok("https:") => "wss",
+        _ => "ws",
+    };
+    let host = location
+        .host()
+        .unwrap_or_else(|_| "localhost:8000".to_string());
+    format!("{}://{}{}", scheme, host, WS_PATH)
+}
+
+/// Opens a socket to `ws_url`, a socket that can't be created is handled like one that
+/// failed to connect, so it goes through the usual reconnect attempts.
+fn create_websocket(ws_url: &str, orders: &mut impl Orders<Msg>) -> Option<WebSocket> {
     let msg_sender = orders.msg_sender();
 
-    WebSocket::builder(WS_URL, orders)
+    let web_socket = WebSocket::builder(ws_url, orders)
         .on_open(|| Msg::WebSocketOpened)
         .on_message(move |msg| decode_message(msg, msg_sender))
         .on_close(Msg::WebSocketClosed)
         .on_error(|| Msg::WebSocketFailed)
-        .build_and_open()
-        .unwrap()
+        .build_and_open();
+    match web_socket {
+        Ok(web_socket) => Some(web_socket),
+        Err(e) => {
+            log!("Failed to create WebSocket", ws_url, e);
+            orders.send_msg(Msg::WebSocketFailed);
+            None
+        }
+    }
 }
 
 fn decode_message(message: WebSocketMessage, msg_sender: Rc<dyn Fn(Option<Msg>)>) {
-    let msg_text = message.text();
-    if msg_text.is_ok() {
-        let msg = message
-            .json::<StateChangeEvent>()
-            .unwrap_or_else(|_| panic!("Failed to decode WebSocket text message: {:?}", msg_text));
-        msg_sender(Some(Msg::StatusChangeEventReceived(msg)));
+    if message.contains_text() {
+        let msg = match message.text() {
+            Ok(text) if text == protocol::PONG_FRAME => Msg::WebSocketPong,
+            Ok(text) => decoded_to_msg(protocol::decode_text(&text)),
+            Err(e) => Msg::WebSocketFrameRejected(DecodeError::Malformed(format!("{:?}", e))),
+        };
+        msg_sender(Some(msg));
+    } else {
+        spawn_local(async move {
+            let decoded = match message.bytes().await {
+                Ok(bytes) => protocol::decode_bytes(&bytes),
+                Err(e) => Err(DecodeError::Malformed(format!("{:?}", e))),
+            };
+            msg_sender(Some(decoded_to_msg(decoded)));
+        });
+    }
+}
+
+fn decoded_to_msg(decoded: Result<StateChangeEvent, DecodeError>) -> Msg {
+    match decoded {
+        Ok(event) => Msg::StatusChangeEventReceived(event),
+        Err(error) => Msg::WebSocketFrameRejected(error),
     }
 }
@@ -0,0 +1,274 @@
+//! Publishes the playing track to the browser Media Session API, so the OS lock screen,
+//! notification area and hardware media keys can show and control the player.
+//!
+//! The API is missing in some browsers, every function is a no-op there.
+//!
+//! The music plays on the backend, not in this page. Mobile browsers only show lock screen
+//! controls and route headset keys to a page with playing media, so a looping silent audio
+//! element plays while the player does.
+
+use std::cell::{Cell, RefCell};
+
+use api_models::common::Command;
+use api_models::player::{PlayerInfo, PlayerState, Song};
+use seed::{prelude::*, *};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+const SILENCE_SAMPLE_RATE: u32 = 8_000;
+// Chrome doesn't show media controls for media shorter than 5 seconds
+const SILENCE_SECS: u32 = 6;
+
+thread_local! {
+    static SILENCE: RefCell<Option<SilentAudio>> = RefCell::new(None);
+    static PLAYING: Cell<bool> = Cell::new(false);
+}
+
+#[wasm_bindgen]
+extern "C" {
+    type MediaSession;
+
+    #[wasm_bindgen(method, getter)]
+    fn metadata(this: &MediaSession) -> JsValue;
+
+    #[wasm_bindgen(method, setter)]
+    fn set_metadata(this: &MediaSession, metadata: &JsValue);
+
+    #[wasm_bindgen(method, setter = playbackState)]
+    fn set_playback_state(this: &MediaSession, state: &str);
+
+    #[wasm_bindgen(method, catch, js_name = setActionHandler)]
+    fn set_action_handler(
+        this: &MediaSession,
+        action: &str,
+        handler: &js_sys::Function,
+    ) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = setPositionState)]
+    fn set_position_state(this: &MediaSession, state: &JsValue) -> Result<(), JsValue>;
+
+    type MediaMetadata;
+
+    #[wasm_bindgen(constructor, catch)]
+    fn new(init: &JsValue) -> Result<MediaMetadata, JsValue>;
+
+    #[wasm_bindgen(js_name = Audio)]
+    type SilentAudio;
+
+    #[wasm_bindgen(constructor, js_class = Audio, catch)]
+    fn new(src: &str) -> Result<SilentAudio, JsValue>;
+
+    #[wasm_bindgen(method, setter)]
+    fn set_loop(this: &SilentAudio, value: bool);
+
+    #[wasm_bindgen(method, getter)]
+    fn paused(this: &SilentAudio) -> bool;
+
+    #[wasm_bindgen(method)]
+    fn play(this: &SilentAudio) -> js_sys::Promise;
+
+    #[wasm_bindgen(method)]
+    fn pause(this: &SilentAudio);
+}
+
+fn media_session() -> Option<MediaSession> {
+    let navigator = js_sys::Reflect::get(&window(), &"navigator".into()).ok()?;
+    let media_session = js_sys::Reflect::get(&navigator, &"mediaSession".into()).ok()?;
+    if media_session.is_undefined() || media_session.is_null() {
+        None
+    } else {
+        Some(media_session.unchecked_into())
+    }
+}
+
+fn set_property(target: &JsValue, key: &str, value: &JsValue) {
+    let _ = js_sys::Reflect::set(target, &key.into(), value);
+}
+
+/// Maps media session actions to player commands for the lifetime of the app.
+pub fn set_action_handlers(send_command: impl Fn(Command) + Clone + 'static) {
+    let media_session = match media_session() {
+        Some(media_session) => media_session,
+        None => return,
+    };
+    let simple_actions: [(&str, fn() -> Command); 4] = [
+        ("play", || Command::Play),
+        ("pause", || Command::Pause),
+        ("previoustrack", || Command::Prev),
+        ("nexttrack", || Command::Next),
+    ];
+    for (action, command) in simple_actions.iter().copied() {
+        let send_command = send_command.clone();
+        let handler = Closure::wrap(
+            Box::new(move |_: JsValue| send_command(command())) as Box<dyn FnMut(JsValue)>
+        );
+        register(&media_session, action, handler);
+    }
+    let seek_handler = Closure::wrap(Box::new(move |details: JsValue| {
+        let seek_time = js_sys::Reflect::get(&details, &"seekTime".into())
+            .ok()
+            .and_then(|seek_time| seek_time.as_f64());
+        if let Some(seek_time) = seek_time {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let seek_time = seek_time.max(0.0).min(f64::from(u16::MAX)) as u16;
+            send_command(Command::Seek(seek_time));
+        }
+    }) as Box<dyn FnMut(JsValue)>);
+    register(&media_session, "seekto", seek_handler);
+
+    // browsers may refuse to start audio outside of a user gesture, e.g. iOS Safari,
+    // so any tap retries starting the silent audio
+    let gesture_handler =
+        Closure::wrap(Box::new(|_: JsValue| sync_silence()) as Box<dyn FnMut(JsValue)>);
+    if document()
+        .add_event_listener_with_callback("click", gesture_handler.as_ref().unchecked_ref())
+        .is_ok()
+    {
+        gesture_handler.forget();
+    }
+}
+
+/// Plays the silent audio while the player plays, so the page counts as playing media.
+pub fn set_playing(playing: bool) {
+    if media_session().is_none() {
+        return;
+    }
+    PLAYING.with(|current| current.set(playing));
+    sync_silence();
+}
+
+fn sync_silence() {
+    let playing = PLAYING.with(Cell::get);
+    SILENCE.with(|silence| {
+        let mut silence = silence.borrow_mut();
+        if silence.is_none() && playing {
+            *silence = silent_audio();
+        }
+        let audio = match silence.as_ref() {
+            Some(audio) => audio,
+            None => return,
+        };
+        if playing && audio.paused() {
+            let started = JsFuture::from(audio.play());
+            spawn_local(async move {
+                if let Err(e) = started.await {
+                    log!("Silent audio for the media controls was not started", e);
+                }
+            });
+        } else if !playing && !audio.paused() {
+            audio.pause();
+        }
+    });
+}
+
+fn silent_audio() -> Option<SilentAudio> {
+    let audio = SilentAudio::new(&silent_wav_url()?).ok()?;
+    audio.set_loop(true);
+    Some(audio)
+}
+
+/// `data:` url of a mono 8 bit PCM wav file with `SILENCE_SECS` of silence.
+fn silent_wav_url() -> Option<String> {
+    let samples = SILENCE_SAMPLE_RATE * SILENCE_SECS;
+    let mut wav = Vec::with_capacity(44 + samples as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + samples).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16_u32.to_le_bytes());
+    // PCM, one channel
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    // sample rate and byte rate are the same with one byte per sample
+    wav.extend_from_slice(&SILENCE_SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&SILENCE_SAMPLE_RATE.to_le_bytes());
+    // block align and bits per sample
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&8_u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&samples.to_le_bytes());
+    // unsigned 8 bit samples are silent at their midpoint
+    wav.resize(44 + samples as usize, 0x80);
+    let binary: String = wav.into_iter().map(char::from).collect();
+    let base64 = window().btoa(&binary).ok()?;
+    Some(format!("data:audio/wav;base64,{}", base64))
+}
+
+fn register(media_session: &MediaSession, action: &str, handler: Closure<dyn FnMut(JsValue)>) {
+    // browsers throw for actions they don't support
+    if media_session
+        .set_action_handler(action, handler.as_ref().unchecked_ref())
+        .is_ok()
+    {
+        handler.forget();
+    }
+}
+
+pub fn update_metadata(track: Option<&Song>, artwork_url: Option<&str>) {
+    let media_session = match media_session() {
+        Some(media_session) => media_session,
+        None => return,
+    };
+    let track = match track {
+        Some(track) => track,
+        None => {
+            media_session.set_metadata(&JsValue::NULL);
+            return;
+        }
+    };
+    let init = js_sys::Object::new();
+    set_property(&init, "title", &track.get_title().into());
+    if let Some(artist) = track.artist.as_deref() {
+        set_property(&init, "artist", &artist.into());
+    }
+    if let Some(album) = track.album.as_deref() {
+        set_property(&init, "album", &album.into());
+    }
+    if let Some(artwork_url) = track.uri.as_deref().or(artwork_url) {
+        set_property(&init, "artwork", &artwork(artwork_url));
+    }
+    match MediaMetadata::new(&init) {
+        Ok(metadata) => media_session.set_metadata(&metadata),
+        Err(e) => log!("Failed to create media metadata", e),
+    }
+}
+
+/// Sets the artwork of the already published track, e.g. once its cover is resolved.
+pub fn update_artwork(artwork_url: &str) {
+    if let Some(media_session) = media_session() {
+        let metadata = media_session.metadata();
+        if !metadata.is_null() && !metadata.is_undefined() {
+            set_property(&metadata, "artwork", &artwork(artwork_url));
+        }
+    }
+}
+
+fn artwork(url: &str) -> JsValue {
+    let image = js_sys::Object::new();
+    set_property(&image, "src", &url.into());
+    js_sys::Array::of1(&image).into()
+}
+
+pub fn update_position(player_info: &PlayerInfo) {
+    let media_session = match media_session() {
+        Some(media_session) => media_session,
+        None => return,
+    };
+    let state = match player_info.state.as_ref() {
+        Some(PlayerState::PLAYING) => "playing",
+        Some(PlayerState::PAUSED) => "paused",
+        _ => "none",
+    };
+    media_session.set_playback_state(state);
+
+    let duration = player_info.time.1.as_secs_f64();
+    if duration > 0.0 {
+        let position_state = js_sys::Object::new();
+        set_property(&position_state, "duration", &duration.into());
+        set_property(&position_state, "playbackRate", &JsValue::from_f64(1.0));
+        set_property(
+            &position_state,
+            "position",
+            &player_info.time.0.as_secs_f64().min(duration).into(),
+        );
+        let _ = media_session.set_position_state(&position_state);
+    }
+}
@@ -1,9 +1,16 @@
-use api_models::player::*;
 use api_models::common::*;
+use api_models::player::*;
 use api_models::state::*;
 use seed::{prelude::*, *};
 
-use std::str::FromStr;
+use std::convert::TryFrom;
+
+use crate::command_queue::CommandsExpired;
+use crate::store::PlayerStore;
+use crate::ui_settings;
+
+const VOLUME_DEBOUNCE_MS: u32 = 150;
+const MUTED_VOLUME_STORAGE_KEY: &str = "dplayer-muted-volume";
 
 // ------ ------
 //     Model
@@ -11,33 +18,75 @@ use std::str::FromStr;
 
 #[derive(Debug)]
 pub struct Model {
-    streamer_status: StreamerState,
-    player_info: Option<PlayerInfo>,
-    current_track_info: Option<Song>,
+    seek_target: Option<u64>,
+    seek_dragging: bool,
+    seek_step_secs: u64,
+    volume_draft: Option<i64>,
+    volume_debounce: Option<CmdHandle>,
+    // volume to restore on unmute, persisted so a reload doesn't lose it
+    muted_volume: Option<i64>,
     waiting_response: bool,
-    remote_error: Option<String>,
+    _event_sub: SubHandle,
+    _expired_sub: SubHandle,
 }
+
 #[derive(Debug)]
 
 pub enum Msg {
     StatusChangeEventReceived(StateChangeEvent),
-    AlbumImageUpdated(Image),
     SendCommand(Command),
-    CurrentStatusFetched(fetch::Result<LastState>),
+    SeekDragged(String),
+    SeekTo(u64),
+    VolumeDragged(String),
+    VolumeReleased(String),
+    SendVolume,
+    Mute { restore_to: i64, min: i64 },
+    Unmute,
+    CommandsExpired(CommandsExpired),
 }
-#[derive(Debug, serde::Deserialize)]
-pub struct AlbumInfo {
-    pub album: Album,
-}
-#[derive(Debug, serde::Deserialize)]
-pub struct Album {
-    image: Vec<Image>,
+
+/// Playback modes next to shuffle, modes the player backend lacks are reported as `None`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlaybackMode {
+    Repeat,
+    Single,
+    Consume,
 }
-#[derive(Debug, serde::Deserialize)]
-pub struct Image {
-    size: String,
-    #[serde(rename = "#text")]
-    text: String,
+
+impl PlaybackMode {
+    const ALL: [Self; 3] = [Self::Repeat, Self::Single, Self::Consume];
+
+    const fn state(self, player_info: &PlayerInfo) -> Option<bool> {
+        match self {
+            Self::Repeat => player_info.repeat,
+            Self::Single => player_info.single,
+            Self::Consume => player_info.consume,
+        }
+    }
+
+    const fn toggle_command(self) -> Command {
+        match self {
+            Self::Repeat => Command::RepeatToggle,
+            Self::Single => Command::SingleToggle,
+            Self::Consume => Command::ConsumeToggle,
+        }
+    }
+
+    const fn icon(self) -> &'static str {
+        match self {
+            Self::Repeat => "repeat",
+            Self::Single => "repeat_one",
+            Self::Consume => "delete_sweep",
+        }
+    }
+
+    const fn title(self) -> &'static str {
+        match self {
+            Self::Repeat => "Repeat",
+            Self::Single => "Repeat single song",
+            Self::Consume => "Remove played songs from the queue",
+        }
+    }
 }
 
 // ------ ------
@@ -45,16 +94,16 @@ pub struct Image {
 // ------ ------
 
 pub(crate) fn init(_: Url, orders: &mut impl Orders<Msg>) -> Model {
-    orders.perform_cmd(async { Msg::CurrentStatusFetched(get_current_status().await) });
     Model {
-        streamer_status: StreamerState {
-            selected_audio_output: AudioOut::SPKR,
-            volume_state: VolumeState::default(),
-        },
-        player_info: None,
-        current_track_info: None,
+        seek_target: None,
+        seek_dragging: false,
+        seek_step_secs: ui_settings::load().seek_step_secs,
+        volume_draft: None,
+        volume_debounce: None,
+        muted_volume: LocalStorage::get(MUTED_VOLUME_STORAGE_KEY).ok(),
         waiting_response: false,
-        remote_error: None,
+        _event_sub: orders.subscribe_with_handle(Msg::StatusChangeEventReceived),
+        _expired_sub: orders.subscribe_with_handle(Msg::CommandsExpired),
     }
 }
 
@@ -64,69 +113,130 @@ pub(crate) fn init(_: Url, orders: &mut impl Orders<Msg>) -> Model {
 
 pub(crate) fn update(msg: Msg, mut model: &mut Model, orders: &mut impl Orders<Msg>) {
     match msg {
-        Msg::AlbumImageUpdated(image) => {
-            model.current_track_info.as_mut().unwrap().uri = Some(image.text);
-        }
-        Msg::CurrentStatusFetched(Ok(st)) => {
-            let track = st.current_track_info.clone();
-            model.current_track_info = st.current_track_info;
-            model.player_info = st.player_info;
-            if let Some(status) = st.streamer_state {
-                model.streamer_status = status;
-            }
-
-            if let Some(track) = track {
-                if track.uri.is_none() {
-                    orders.perform_cmd(async { update_album_cover(track).await });
-                }
-            }
-        }
-        Msg::StatusChangeEventReceived(StateChangeEvent::CurrentTrackInfoChanged(track_info)) => {
+        Msg::StatusChangeEventReceived(StateChangeEvent::CurrentTrackInfoChanged(_)) => {
             model.waiting_response = false;
-            let ps = track_info.clone();
-            model.current_track_info = Some(track_info);
-            if ps.uri.is_none() {
-                orders.perform_cmd(async { update_album_cover(ps).await });
-            }
         }
 
-        Msg::StatusChangeEventReceived(StateChangeEvent::PlayerInfoChanged(player_info)) => {
+        Msg::StatusChangeEventReceived(StateChangeEvent::PlayerInfoChanged(_)) => {
             model.waiting_response = false;
-            model.player_info = Some(player_info);
+            if !model.seek_dragging {
+                model.seek_target = None;
+            }
         }
 
-        Msg::StatusChangeEventReceived(StateChangeEvent::StreamerStateChanged(
-            streamer_status,
-        )) => {
+        Msg::StatusChangeEventReceived(StateChangeEvent::StreamerStateChanged(streamer_state)) => {
             model.waiting_response = false;
-            model.streamer_status = streamer_status;
+            // volume was raised elsewhere while muted
+            if model.muted_volume.is_some()
+                && streamer_state.volume_state.volume > streamer_state.volume_state.min
+            {
+                set_muted_volume(model, None);
+            }
+            // the backend value wins unless the user is still dragging the slider
+            if model.volume_debounce.is_none() {
+                model.volume_draft = None;
+            }
         }
 
-        Msg::StatusChangeEventReceived(StateChangeEvent::Error(error)) => {
-            model.remote_error = Some(error)
-        }
         Msg::StatusChangeEventReceived(_) => {}
         Msg::SendCommand(cmd) => {
             log!("Player {}", cmd);
-            match cmd {
-                Command::SwitchToPlayer(_) => model.waiting_response = true,
-                Command::SetVol(vol) => model.streamer_status.volume_state.volume = vol as i64,
-                _ => (),
+            if let Command::SwitchToPlayer(_) = cmd {
+                model.waiting_response = true;
+            }
+        }
+        Msg::SeekDragged(value) => {
+            if let Ok(secs) = value.parse::<u64>() {
+                model.seek_dragging = true;
+                model.seek_target = Some(secs);
             }
         }
-        _ => {
-            log!("Unknown variant");
+        Msg::SeekTo(secs) => {
+            // keep showing the target until the backend reports the new position
+            model.seek_dragging = false;
+            model.seek_target = Some(secs);
+            let secs = u16::try_from(secs).unwrap_or(u16::MAX);
+            orders.send_msg(Msg::SendCommand(Command::Seek(secs)));
+        }
+        Msg::VolumeDragged(value) => {
+            if let Ok(volume) = value.parse::<i64>() {
+                set_muted_volume(model, None);
+                model.volume_draft = Some(volume);
+                model.volume_debounce = Some(
+                    orders.perform_cmd_with_handle(cmds::timeout(VOLUME_DEBOUNCE_MS, || {
+                        Msg::SendVolume
+                    })),
+                );
+            }
+        }
+        Msg::VolumeReleased(value) => {
+            if let Ok(volume) = value.parse::<i64>() {
+                model.volume_draft = Some(volume);
+                model.volume_debounce = None;
+                orders.send_msg(Msg::SendVolume);
+            }
+        }
+        Msg::SendVolume => {
+            model.volume_debounce = None;
+            if let Some(volume) = model.volume_draft {
+                let volume = u8::try_from(volume.max(0)).unwrap_or(u8::MAX);
+                orders.send_msg(Msg::SendCommand(Command::SetVol(volume)));
+            }
+        }
+        Msg::Mute { restore_to, min } => {
+            // a volume that is still being dragged or sent wins over the last reported one
+            let restore_to = model.volume_draft.unwrap_or(restore_to);
+            model.volume_debounce = None;
+            set_muted_volume(model, Some(restore_to));
+            model.volume_draft = Some(min);
+            orders.send_msg(Msg::SendVolume);
+        }
+        Msg::Unmute => {
+            if let Some(volume) = model.muted_volume {
+                set_muted_volume(model, None);
+                model.volume_draft = Some(volume);
+                orders.send_msg(Msg::SendVolume);
+            }
+        }
+        Msg::CommandsExpired(CommandsExpired(expired)) => {
+            // the slider and mute button would otherwise show a volume the player never got
+            if expired
+                .iter()
+                .any(|command| command.is_kind_of(&Command::SetVol(0)))
+            {
+                model.volume_draft = None;
+                set_muted_volume(model, None);
+            }
         }
     }
 }
 
+fn set_muted_volume(model: &mut Model, muted_volume: Option<i64>) {
+    let stored = match muted_volume {
+        Some(volume) => LocalStorage::insert(MUTED_VOLUME_STORAGE_KEY, &volume),
+        None => LocalStorage::remove(MUTED_VOLUME_STORAGE_KEY),
+    };
+    if let Err(e) = stored {
+        log!("Failed to store muted volume", e);
+    }
+    model.muted_volume = muted_volume;
+}
+
 // ------ ------
 //     View
 // ------ ------
-pub(crate) fn view(model: &Model) -> Node<Msg> {
+pub(crate) fn view(model: &Model, player_store: &PlayerStore, connected: bool) -> Node<Msg> {
+    let default_streamer_state = StreamerState {
+        selected_audio_output: AudioOut::SPKR,
+        volume_state: VolumeState::default(),
+    };
+    let streamer_state = player_store
+        .streamer_state
+        .as_ref()
+        .unwrap_or(&default_streamer_state);
     div![
         style! {
-            St::BackgroundImage => get_background_image(model),
+            St::BackgroundImage => get_background_image(player_store),
             St::BackgroundRepeat => "no-repeat",
             St::BackgroundSize => "cover",
             St::MinHeight => "95vh"
@@ -137,13 +247,18 @@ pub(crate) fn view(model: &Model) -> Node<Msg> {
                 St::MinHeight => "95vh"
             },
             view_track_info(
-                model.current_track_info.as_ref(),
-                model.player_info.as_ref()
+                player_store.current_track_info.as_ref(),
+                player_store.player_info.as_ref()
+            ),
+            view_track_progress_bar(model, player_store, connected),
+            view_volume_slider(model, &streamer_state.volume_state, connected),
+            view_controls(
+                player_store.is_playing(),
+                &streamer_state.volume_state,
+                model.muted_volume.is_some(),
+                connected
             ),
-            view_track_progress_bar(model.player_info.as_ref()),
-            view_volume_slider(&model.streamer_status.volume_state),
-            view_controls(model.player_info.as_ref()),
-            view_controls_down(model.player_info.as_ref(), &model.streamer_status),
+            view_controls_down(player_store.player_info.as_ref(), streamer_state, connected),
         ]
     ]
 }
@@ -252,8 +367,14 @@ fn view_track_info(status: Option<&Song>, player_info: Option<&PlayerInfo>) -> N
     }
 }
 
-fn view_track_progress_bar(player_info: Option<&PlayerInfo>) -> Node<Msg> {
-    if let Some(player_info) = player_info {
+fn view_track_progress_bar(
+    model: &Model,
+    player_store: &PlayerStore,
+    connected: bool,
+) -> Node<Msg> {
+    if let (Some(elapsed), Some(total)) = (player_store.elapsed_secs(), player_store.total_secs()) {
+        let elapsed = model.seek_target.unwrap_or(elapsed);
+        let step = model.seek_step_secs;
         div![
             style! {
                 St::Padding => "1.2rem",
@@ -265,27 +386,62 @@ fn view_track_progress_bar(player_info: Option<&PlayerInfo>) -> Node<Msg> {
                     "has-text-light",
                     "has-background-dark-transparent"
                 ],
-                player_info.format_time()
+                format!("{} / {}", format_secs(elapsed), format_secs(total))
             ],
-            progress![
-                C!["progress", "is-small", "is-success"],
-                attrs! {"value"=> player_info.time.0.as_secs()},
-                attrs! {"max"=> player_info.time.1.as_secs()},
-                player_info.time.0.as_secs()
+            input![
+                C![
+                    "slider",
+                    "is-fullwidth",
+                    "is-small",
+                    "is-success",
+                    "seek-slider"
+                ],
+                attrs! {"value"=> elapsed},
+                attrs! {"step"=> 1},
+                attrs! {"max"=> total},
+                attrs! {"min"=> 0},
+                attrs! {"type"=> "range"},
+                IF!(!connected || total == 0 => attrs!{"disabled"=>true}),
+                input_ev(Ev::Input, Msg::SeekDragged),
+                input_ev(Ev::Change, |selected| selected
+                    .parse::<u64>()
+                    .ok()
+                    .map(Msg::SeekTo)),
+                keyboard_ev(Ev::KeyDown, move |event| {
+                    let target = match event.key().as_str() {
+                        "ArrowLeft" => elapsed.saturating_sub(step),
+                        "ArrowRight" => (elapsed + step).min(total),
+                        _ => return None,
+                    };
+                    event.prevent_default();
+                    Some(Msg::SeekTo(target))
+                }),
             ],
         ]
     } else {
         empty!()
     }
 }
-fn view_controls(player_info: Option<&PlayerInfo>) -> Node<Msg> {
-    let playing = player_info.map_or(false, |f| {
-        f.state
-            .as_ref()
-            .map_or(false, |f| *f == PlayerState::PLAYING)
-    });
+
+pub(crate) fn format_secs(secs: u64) -> String {
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+fn view_controls(
+    playing: bool,
+    volume_state: &VolumeState,
+    muted: bool,
+    connected: bool,
+) -> Node<Msg> {
+    let volume = volume_state.volume;
+    let min = volume_state.min;
+    let mute_icon = if muted {
+        "fas fa-volume-mute"
+    } else {
+        "fas fa-volume-off"
+    };
     div![
         C!["transparent"],
+        IF!(!connected => C!["is-disconnected"]),
         nav![
             C!["level is-mobile"],
             div![
@@ -295,7 +451,7 @@ fn view_controls(player_info: Option<&PlayerInfo>) -> Node<Msg> {
                     div![
                         C!["level-item"],
                         button![
-                            IF!(playing => attrs!{"disabled"=>true}),
+                            IF!(playing || !connected => attrs!{"disabled"=>true}),
                             C!["button"],
                             ev(Ev::Click, |_| Msg::SendCommand(Command::Play)),
                             span![C!("icon"), i![C!("fas fa-play")]]
@@ -304,7 +460,7 @@ fn view_controls(player_info: Option<&PlayerInfo>) -> Node<Msg> {
                     div![
                         C!["level-item"],
                         button![
-                            IF!(!playing => attrs!{"disabled"=>true}),
+                            IF!(!playing || !connected => attrs!{"disabled"=>true}),
                             C!["button"],
                             span![C!("icon"), i![C!("fas fa-stop")]],
                             ev(Ev::Click, |_| Msg::SendCommand(Command::Pause))
@@ -313,6 +469,7 @@ fn view_controls(player_info: Option<&PlayerInfo>) -> Node<Msg> {
                     div![
                         C!["level-item"],
                         button![
+                            IF!(!connected => attrs!{"disabled"=>true}),
                             C!["button"],
                             span![C!("icon"), i![C!("fas fa-step-backward")]],
                             ev(Ev::Click, |_| Msg::SendCommand(Command::Prev))
@@ -321,6 +478,7 @@ fn view_controls(player_info: Option<&PlayerInfo>) -> Node<Msg> {
                     div![
                         C!["level-item"],
                         button![
+                            IF!(!connected => attrs!{"disabled"=>true}),
                             C!["button"],
                             span![C!("icon"), i![C!("fas fa-step-forward")]],
                             ev(Ev::Click, |_| Msg::SendCommand(Command::Next))
@@ -329,6 +487,7 @@ fn view_controls(player_info: Option<&PlayerInfo>) -> Node<Msg> {
                     div![
                         C!["level-item"],
                         button![
+                            IF!(!connected => attrs!{"disabled"=>true}),
                             C!["button"],
                             span![C!("icon"), i![C!("fas fa-volume-down")]],
                             ev(Ev::Click, |_| Msg::SendCommand(Command::VolDown))
@@ -337,11 +496,29 @@ fn view_controls(player_info: Option<&PlayerInfo>) -> Node<Msg> {
                     div![
                         C!["level-item"],
                         button![
+                            IF!(!connected => attrs!{"disabled"=>true}),
                             C!["button"],
                             span![C!("icon"), i![C!("fas fa-volume-up")]],
                             ev(Ev::Click, |_| Msg::SendCommand(Command::VolUp))
                         ]
                     ],
+                    div![
+                        C!["level-item"],
+                        button![
+                            IF!(!connected => attrs!{"disabled"=>true}),
+                            C!["button", IF!(muted => "is-warning")],
+                            attrs! {At::Title => if muted { "Unmute" } else { "Mute" }},
+                            span![C!("icon"), i![C!(mute_icon)]],
+                            ev(Ev::Click, move |_| if muted {
+                                Msg::Unmute
+                            } else {
+                                Msg::Mute {
+                                    restore_to: volume,
+                                    min,
+                                }
+                            })
+                        ]
+                    ],
                 ],
             ]
         ]
@@ -351,6 +528,7 @@ fn view_controls(player_info: Option<&PlayerInfo>) -> Node<Msg> {
 fn view_controls_down(
     player_info: Option<&PlayerInfo>,
     streamer_status: &StreamerState,
+    connected: bool,
 ) -> Node<Msg> {
     let audio_out = match streamer_status.selected_audio_output {
         AudioOut::SPKR => "speaker",
@@ -366,6 +544,7 @@ fn view_controls_down(
 
     div![
         C!["transparent"],
+        IF!(!connected => C!["is-disconnected"]),
         nav![
             C!["level is-mobile"],
             div![
@@ -375,14 +554,30 @@ fn view_controls_down(
                     div![
                         C!["level-item"],
                         button![
+                            IF!(!connected => attrs!{"disabled"=>true}),
                             C!["button"],
                             span![C!["icon"], i![C!("material-icons"), shuffle]],
                             ev(Ev::Click, |_| Msg::SendCommand(Command::RandomToggle)),
                         ]
                     ],
+                    player_info.map(|pi| PlaybackMode::ALL
+                        .iter()
+                        .filter_map(|mode| mode.state(pi).map(|enabled| (*mode, enabled)))
+                        .map(|(mode, enabled)| div![
+                            C!["level-item"],
+                            button![
+                                IF!(!connected => attrs!{"disabled"=>true}),
+                                C!["button", IF!(enabled => "is-success")],
+                                attrs! {At::Title => mode.title()},
+                                span![C!["icon"], i![C!("material-icons"), mode.icon()]],
+                                ev(Ev::Click, move |_| Msg::SendCommand(mode.toggle_command())),
+                            ]
+                        ])
+                        .collect::<Vec<_>>()),
                     div![
                         C!["level-item"],
                         button![
+                            IF!(!connected => attrs!{"disabled"=>true}),
                             C!["button"],
                             span![C!["icon"], i![C!("material-icons"), audio_out]],
                             ev(Ev::Click, |_| Msg::SendCommand(Command::ChangeAudioOutput))
@@ -394,7 +589,8 @@ fn view_controls_down(
     ]
 }
 
-fn view_volume_slider(dac_status: &VolumeState) -> Node<Msg> {
+fn view_volume_slider(model: &Model, volume_state: &VolumeState, connected: bool) -> Node<Msg> {
+    let volume = model.volume_draft.unwrap_or(volume_state.volume);
     div![
         style! {
             St::Padding => "1.2rem",
@@ -406,78 +602,55 @@ fn view_volume_slider(dac_status: &VolumeState) -> Node<Msg> {
                 "has-text-light",
                 "has-background-dark-transparent"
             ],
-            format!("Volume: {}/{}", dac_status.volume, 255)
+            format!("Volume: {}", format_volume(volume, volume_state))
         ],
         input![
             C!["slider", "is-fullwidth", "is-success"],
             style! {
                 St::PaddingRight => "1.2rem"
             },
-            attrs! {"value"=> dac_status.volume},
-            attrs! {"step"=> 1},
-            attrs! {"max"=> 255},
-            attrs! {"min"=> 140},
+            attrs! {"value"=> volume},
+            attrs! {"step"=> volume_state.step.max(1)},
+            attrs! {"max"=> volume_state.max},
+            attrs! {"min"=> volume_state.min},
             attrs! {"type"=> "range"},
-            attrs! {"disabled"=> "disabled"},
-            input_ev(Ev::Change, move |selected| Msg::SendCommand(
-                Command::SetVol(u8::from_str(selected.as_str()).unwrap())
-            )),
+            IF!(!connected || volume_state.max <= volume_state.min => attrs!{"disabled"=>true}),
+            input_ev(Ev::Input, Msg::VolumeDragged),
+            input_ev(Ev::Change, Msg::VolumeReleased),
         ],
     ]
 }
 
-fn get_background_image(model: &Model) -> String {
-    if let Some(ps) = model.current_track_info.as_ref() {
-        format!("url({})", ps.uri.as_ref().map_or("/no_album.png", |f| f))
+/// Formats the volume in dB when the output reports its attenuation per step,
+/// otherwise as a percentage of the backend range.
+#[allow(clippy::cast_precision_loss)]
+fn format_volume(volume: i64, volume_state: &VolumeState) -> String {
+    if let Some(db_per_step) = volume_state.db_per_step {
+        format!("{:.1} dB", (volume - volume_state.max) as f64 * db_per_step)
+    } else if volume_state.max > volume_state.min {
+        format!(
+            "{}%",
+            (volume - volume_state.min) * 100 / (volume_state.max - volume_state.min)
+        )
     } else {
-        String::new()
+        volume.to_string()
     }
 }
 
-pub async fn get_current_status() -> fetch::Result<LastState> {
-    Request::new("/api/status")
-        .method(Method::Get)
-        .fetch()
-        .await?
-        .check_status()?
-        .json::<LastState>()
-        .await
-}
-
-pub async fn update_album_cover(track: Song) -> Msg {
-    if track.album.is_some() && track.artist.is_some() {
-        let ai = get_album_image_from_lastfm_api(track.album.unwrap(), track.artist.unwrap()).await;
-        match ai {
-            Some(ai) => Msg::AlbumImageUpdated(ai),
-            None => Msg::AlbumImageUpdated(Image {
-                size: "mega".to_string(),
-                text: "/no_album.png".to_string(),
-            }),
-        }
+fn get_background_image(player_store: &PlayerStore) -> String {
+    if let Some(ps) = player_store.current_track_info.as_ref() {
+        let image = ps.uri.as_ref().or_else(|| player_store.album_art.as_ref());
+        css_url(image.map_or("/no_album.png", |f| f))
     } else {
-        Msg::AlbumImageUpdated(Image {
-            size: "mega".to_string(),
-            text: "/no_album.png".to_string(),
-        })
+        String::new()
     }
 }
-async fn get_album_image_from_lastfm_api(album: String, artist: String) -> Option<Image> {
-    let response = fetch(format!("http://ws.audioscrobbler.com/2.0/?method=album.getinfo&album={}&artist={}&api_key=3b3df6c5dd3ad07222adc8dd3ccd8cdc&format=json", album, artist)).await;
-    if let Ok(response) = response {
-        let info = response.json::<AlbumInfo>().await;
-        if let Ok(info) = info {
-            info.album
-                .image
-                .into_iter()
-                .find(|i| i.size == "mega" && !i.text.is_empty())
-        } else {
-            log!("Failed to get album info {}", info);
-            None
-        }
-    } else if let Err(e) = response {
-        log!("Error getting album info from last.fm {}", e);
-        None
-    } else {
-        None
-    }
+
+/// Quoted CSS `url()`, art urls built from file paths may contain spaces and parentheses.
+fn css_url(url: &str) -> String {
+    let escaped = url
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\a ");
+    format!("url(\"{}\")", escaped)
 }
@@ -1,34 +1,41 @@
-use api_models::{
-    player::*,
-    common::*,
-    playlist::Playlist,
-};
+use api_models::{common::*, player::*, playlist::Playlist, state::StateChangeEvent};
 use seed::{prelude::*, *};
+use std::collections::HashMap;
+
+use crate::album_art;
+use crate::api;
+use crate::toast::Toast;
 
 #[derive(Debug)]
 pub struct Model {
     pub playlists: Vec<Playlist>,
     pub playlist_items: Vec<Song>,
     pub selected_playlist_id: Option<String>,
-    pub waiting_response: bool
+    pub waiting_response: bool,
+    thumbnails: HashMap<String, String>,
+    _event_sub: SubHandle,
 }
 pub enum Msg {
-    PlaylistsFetched(fetch::Result<Vec<Playlist>>),
-    PlaylistItemsFetched(fetch::Result<Vec<Song>>),
+    PlaylistsFetched(api::Result<Vec<Playlist>>),
+    PlaylistItemsFetched(api::Result<Vec<Song>>),
 
     SendCommand(Command),
     SelectPlaylist(String),
 
     LoadPlaylistIntoQueue,
+    ThumbnailResolved(String, String),
+    StatusChangeEventReceived(StateChangeEvent),
 }
 
 pub(crate) fn init(_url: Url, orders: &mut impl Orders<Msg>) -> Model {
-    orders.perform_cmd(async { Msg::PlaylistsFetched(get_playlists().await) });
+    orders.perform_cmd(async { Msg::PlaylistsFetched(api::get_playlists().await) });
     Model {
         playlists: Vec::new(),
         playlist_items: Vec::new(),
         selected_playlist_id: None,
-        waiting_response: false
+        waiting_response: false,
+        thumbnails: HashMap::new(),
+        _event_sub: orders.subscribe_with_handle(Msg::StatusChangeEventReceived),
     }
 }
 
@@ -38,17 +45,41 @@ pub(crate) fn init(_url: Url, orders: &mut impl Orders<Msg>) -> Model {
 
 pub(crate) fn update(msg: Msg, mut model: &mut Model, orders: &mut impl Orders<Msg>) {
     match msg {
-        Msg::PlaylistsFetched(pls) => model.playlists = pls.unwrap_or_default(),
+        Msg::StatusChangeEventReceived(StateChangeEvent::PlaylistsChanged { .. }) => {
+            orders
+                .skip()
+                .perform_cmd(async { Msg::PlaylistsFetched(api::get_playlists().await) });
+        }
+        Msg::StatusChangeEventReceived(_) => {
+            orders.skip();
+        }
+        Msg::PlaylistsFetched(Ok(pls)) => model.playlists = pls,
+        Msg::PlaylistsFetched(Err(e)) => {
+            log!("Failed to fetch playlists", e);
+            orders.notify(Toast::error(format!("Failed to load playlists: {}", e)));
+        }
         Msg::SelectPlaylist(pl_id) => {
             model.waiting_response = true;
             model.selected_playlist_id = Some(pl_id.clone());
-            orders
-                .perform_cmd(async { Msg::PlaylistItemsFetched(get_playlist_items(pl_id).await) });
+            orders.perform_cmd(async move {
+                Msg::PlaylistItemsFetched(api::get_playlist_items(&pl_id).await)
+            });
         }
         Msg::SendCommand(cmd) => log!("Cmd:", cmd),
         Msg::PlaylistItemsFetched(pl_items) => {
             model.waiting_response = false;
-            model.playlist_items = pl_items.unwrap_or_default()},
+            match pl_items {
+                Ok(items) => model.playlist_items = items,
+                Err(e) => {
+                    log!("Failed to fetch playlist items", e);
+                    orders.notify(Toast::error(format!("Failed to load the playlist: {}", e)));
+                }
+            }
+            album_art::request_thumbnails(&model.playlist_items, orders, Msg::ThumbnailResolved);
+        }
+        Msg::ThumbnailResolved(album_key, url) => {
+            model.thumbnails.insert(album_key, url);
+        }
         Msg::LoadPlaylistIntoQueue => {
             model.selected_playlist_id.clone().map(|pl| {
                 orders.perform_cmd(async { Msg::SendCommand(Command::LoadPlaylist(pl)) })
@@ -100,6 +131,7 @@ fn view_playlist_items(model: &Model) -> Node<Msg> {
         ],
         model.playlist_items.iter().map(|it| div![
             C!["list-item"],
+            album_art::view_thumbnail(&model.thumbnails, it),
             div![
                 C!["list-item-content"],
                 div![C!["list-item-title"], it.info_string()],
@@ -107,22 +139,3 @@ fn view_playlist_items(model: &Model) -> Node<Msg> {
         ])
     ]]
 }
-
-pub async fn get_playlists() -> fetch::Result<Vec<Playlist>> {
-    Request::new("/api/playlist")
-        .method(Method::Get)
-        .fetch()
-        .await?
-        .check_status()?
-        .json::<Vec<Playlist>>()
-        .await
-}
-pub async fn get_playlist_items(pl_id: String) -> fetch::Result<Vec<Song>> {
-    Request::new(format!("/api/playlist/{}", pl_id))
-        .method(Method::Get)
-        .fetch()
-        .await?
-        .check_status()?
-        .json::<Vec<Song>>()
-        .await
-}
@@ -1,23 +1,142 @@
-use api_models::player::Song;
+use std::collections::{BTreeSet, HashMap};
+
 use api_models::common::Command;
+use api_models::player::*;
+use api_models::playlist::Playlist;
+use api_models::state::*;
 use seed::{prelude::*, *};
 
+use crate::album_art;
+use crate::api;
+use crate::command_queue::COMMAND_TIMEOUT_MS;
+use crate::page::player::format_secs;
+use crate::store::PlayerStore;
+use crate::toast::Toast;
+
+// adding a whole album produces a burst of queue changes, refresh once they settle
+const QUEUE_REFRESH_DEBOUNCE_MS: u32 = 300;
+const QUEUE_INDEX_ATTR: &str = "data-queue-index";
+
 #[derive(Debug)]
 pub struct Model {
     pub queue_items: Vec<Song>,
+    thumbnails: HashMap<String, String>,
     waiting_response: bool,
+    playing_item: ElRef<web_sys::Element>,
+    refresh_debounce: Option<CmdHandle>,
+    // responses of superseded refreshes are dropped, they may arrive out of order
+    refresh_generation: u64,
+    drag: Option<Drag>,
+    pending_move: Option<PendingMove>,
+    // indexes into `queue_items`, cleared whenever the items are replaced
+    selected: BTreeSet<usize>,
+    confirmation: Option<QueueAction>,
+    save_dialog: Option<SaveDialog>,
+    // existing playlists, to warn before one is overwritten
+    playlists: Vec<Playlist>,
+    pending_save: Option<PendingSave>,
+    _event_sub: SubHandle,
+}
+
+#[derive(Debug, Default)]
+struct SaveDialog {
+    name: String,
+}
+
+/// A playlist save sent to the backend, confirmed once the playlists change and include it.
+#[derive(Debug)]
+struct PendingSave {
+    name: String,
+    playlists_changed: bool,
+    _confirmation_timeout: CmdHandle,
+}
+
+/// Removing songs from the queue.
+///
+/// Songs are kept instead of row indexes, the queue may be refreshed while the user
+/// confirms and they are looked up again by position and file when the action is applied.
+#[derive(Debug, Clone)]
+pub enum QueueAction {
+    Remove(Vec<Song>),
+    Clear,
+    /// Keeps only the given song, the one playing.
+    Crop(Song),
+}
+
+impl QueueAction {
+    fn question(&self) -> String {
+        match self {
+            Self::Remove(songs) if songs.len() == 1 => {
+                "Remove the selected song from the queue?".to_string()
+            }
+            Self::Remove(songs) => {
+                format!("Remove {} selected songs from the queue?", songs.len())
+            }
+            Self::Clear => "Remove all songs from the queue?".to_string(),
+            Self::Crop(_) => "Remove all songs except the one playing?".to_string(),
+        }
+    }
+}
+
+/// Row being dragged and the row it would be dropped on, both as indexes into `queue_items`.
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    from: usize,
+    over: usize,
+}
+
+/// A move applied to `queue_items` before the backend confirmed it.
+#[derive(Debug)]
+struct PendingMove {
+    previous_items: Vec<Song>,
+    _confirmation_timeout: CmdHandle,
 }
 
 pub enum Msg {
-    PlaylistItemsFetched(fetch::Result<Vec<Song>>),
+    PlaylistItemsFetched(api::Result<Vec<Song>>),
     SendCommand(Command),
+    ThumbnailResolved(String, String),
+    StatusChangeEventReceived(StateChangeEvent),
+    ScrollToPlayingItem,
+    RefreshQueue,
+    QueueRefreshed(u64, api::Result<Vec<Song>>),
+    DragStarted(usize),
+    DraggedOver(usize),
+    TouchDragMoved(i32, i32),
+    Dropped,
+    DragCancelled,
+    MoveNotConfirmed,
+    ToggleSelected(usize),
+    RemoveItem(usize),
+    AskConfirmation(QueueAction),
+    ActionConfirmed,
+    ActionCancelled,
+    OpenSaveDialog,
+    PlaylistNameChanged(String),
+    SaveAsPlaylist,
+    SaveDialogClosed,
+    RefreshPlaylists,
+    PlaylistsFetched(api::Result<Vec<Playlist>>),
+    SaveNotConfirmed,
 }
 
 pub(crate) fn init(_url: Url, orders: &mut impl Orders<Msg>) -> Model {
-    orders.perform_cmd(async { Msg::PlaylistItemsFetched(get_queue_items().await) });
+    orders.perform_cmd(async { Msg::PlaylistItemsFetched(api::get_queue().await) });
     Model {
         queue_items: Vec::new(),
+        thumbnails: HashMap::new(),
         waiting_response: true,
+        playing_item: ElRef::default(),
+        refresh_debounce: None,
+        refresh_generation: 0,
+        drag: None,
+        pending_move: None,
+        selected: BTreeSet::new(),
+        confirmation: None,
+        save_dialog: None,
+        playlists: Vec::new(),
+        pending_save: None,
+        _event_sub: orders.subscribe_with_handle(Msg::StatusChangeEventReceived),
     }
 }
 
@@ -25,62 +144,654 @@ pub(crate) fn init(_url: Url, orders: &mut impl Orders<Msg>) -> Model {
 //    Update
 // ------ ------
 
-pub(crate) fn update(msg: Msg, mut model: &mut Model, _orders: &mut impl Orders<Msg>) {
+pub(crate) fn update(msg: Msg, mut model: &mut Model, orders: &mut impl Orders<Msg>) {
     match msg {
         Msg::PlaylistItemsFetched(pl_items) => {
             model.waiting_response = false;
-            model.queue_items = pl_items.unwrap_or_default();
+            match pl_items {
+                Ok(items) => {
+                    model.queue_items = items;
+                    model.selected.clear();
+                    orders.after_next_render(|_| Msg::ScrollToPlayingItem);
+                }
+                Err(e) => {
+                    log!("Failed to fetch queue", e);
+                    orders.notify(Toast::error(format!("Failed to load the queue: {}", e)));
+                }
+            }
+            album_art::request_thumbnails(&model.queue_items, orders, Msg::ThumbnailResolved);
+        }
+        Msg::ThumbnailResolved(album_key, url) => {
+            model.thumbnails.insert(album_key, url);
+        }
+        // the playing item and its elapsed time come from the shared store,
+        // they only have to be re-rendered
+        Msg::StatusChangeEventReceived(
+            StateChangeEvent::CurrentTrackInfoChanged(_) | StateChangeEvent::PlayerInfoChanged(_),
+        ) => {}
+        Msg::StatusChangeEventReceived(StateChangeEvent::QueueChanged { .. }) => {
+            model.refresh_debounce = Some(
+                orders.perform_cmd_with_handle(cmds::timeout(QUEUE_REFRESH_DEBOUNCE_MS, || {
+                    Msg::RefreshQueue
+                })),
+            );
+            orders.skip();
+        }
+        Msg::StatusChangeEventReceived(StateChangeEvent::PlaylistsChanged { .. }) => {
+            if let Some(pending_save) = &mut model.pending_save {
+                pending_save.playlists_changed = true;
+            }
+            if model.pending_save.is_some() || model.save_dialog.is_some() {
+                orders.send_msg(Msg::RefreshPlaylists);
+            }
+            orders.skip();
+        }
+        Msg::StatusChangeEventReceived(_) => {
+            orders.skip();
+        }
+        Msg::RefreshQueue => {
+            model.refresh_debounce = None;
+            model.refresh_generation += 1;
+            let generation = model.refresh_generation;
+            // no spinner, the list stays usable and keeps its scroll position
+            orders.perform_cmd(
+                async move { Msg::QueueRefreshed(generation, api::get_queue().await) },
+            );
+            orders.skip();
+        }
+        Msg::QueueRefreshed(generation, _) if generation != model.refresh_generation => {
+            orders.skip();
+        }
+        Msg::QueueRefreshed(_, Ok(items)) => {
+            // the backend state is authoritative, whether it includes the move or not
+            model.pending_move = None;
+            model.queue_items = items;
+            model.selected.clear();
+            album_art::request_thumbnails(&model.queue_items, orders, Msg::ThumbnailResolved);
+        }
+        Msg::QueueRefreshed(_, Err(e)) => {
+            log!("Failed to refresh queue", e);
+            orders.notify(Toast::warning(format!(
+                "The queue may be out of date: {}",
+                e
+            )));
+        }
+        Msg::ScrollToPlayingItem => {
+            if let Some(element) = model.playing_item.get() {
+                scroll_into_view(&element);
+            }
+        }
+        Msg::DragStarted(index) => {
+            model.drag = Some(Drag {
+                from: index,
+                over: index,
+            });
+        }
+        Msg::DraggedOver(index) => match model.drag.as_mut() {
+            Some(drag) if drag.over != index => drag.over = index,
+            _ => {
+                orders.skip();
+            }
+        },
+        Msg::TouchDragMoved(x, y) => match queue_index_at(x, y) {
+            Some(index) => {
+                orders.send_msg(Msg::DraggedOver(index));
+            }
+            None => {
+                orders.skip();
+            }
+        },
+        Msg::Dropped => {
+            if let Some(Drag { from, over }) = model.drag.take() {
+                move_item(model, from, over, orders);
+            }
+        }
+        Msg::DragCancelled => model.drag = None,
+        // backend errors don't say which command failed, so a move is only rolled back when
+        // no queue change confirmed it in time, then the backend state is fetched again
+        Msg::MoveNotConfirmed => {
+            if let Some(pending_move) = model.pending_move.take() {
+                model.queue_items = pending_move.previous_items;
+                orders.send_msg(Msg::RefreshQueue).notify(Toast::warning(
+                    "Moving the song was not confirmed by the player",
+                ));
+            }
+        }
+        Msg::ToggleSelected(index) => {
+            if !model.selected.remove(&index) {
+                model.selected.insert(index);
+            }
+        }
+        Msg::RemoveItem(index) => {
+            if let Some(song) = model.queue_items.get(index).cloned() {
+                apply_action(model, QueueAction::Remove(vec![song]), orders);
+            }
+        }
+        Msg::AskConfirmation(action) => model.confirmation = Some(action),
+        Msg::ActionConfirmed => {
+            if let Some(action) = model.confirmation.take() {
+                apply_action(model, action, orders);
+            }
+        }
+        Msg::ActionCancelled => model.confirmation = None,
+        Msg::OpenSaveDialog => {
+            model.save_dialog = Some(SaveDialog::default());
+            orders.send_msg(Msg::RefreshPlaylists);
+        }
+        Msg::PlaylistNameChanged(name) => {
+            if let Some(dialog) = model.save_dialog.as_mut() {
+                dialog.name = name;
+            }
+        }
+        Msg::SaveAsPlaylist => {
+            let name = match model.save_dialog.as_ref() {
+                Some(dialog) if !dialog.name.trim().is_empty() => dialog.name.trim().to_string(),
+                _ => return,
+            };
+            model.save_dialog = None;
+            orders.send_msg(Msg::SendCommand(Command::SaveQueueAsPlaylist(name.clone())));
+            model.pending_save = Some(PendingSave {
+                name,
+                playlists_changed: false,
+                _confirmation_timeout: orders
+                    .perform_cmd_with_handle(cmds::timeout(COMMAND_TIMEOUT_MS, || {
+                        Msg::SaveNotConfirmed
+                    })),
+            });
+        }
+        Msg::SaveNotConfirmed => {
+            if let Some(pending_save) = model.pending_save.take() {
+                orders.notify(Toast::error(format!(
+                    "Saving the queue as playlist \"{}\" was not confirmed by the player",
+                    pending_save.name
+                )));
+            }
+        }
+        Msg::SaveDialogClosed => model.save_dialog = None,
+        Msg::RefreshPlaylists => {
+            orders.perform_cmd(async { Msg::PlaylistsFetched(api::get_playlists().await) });
+        }
+        Msg::PlaylistsFetched(Ok(playlists)) => {
+            model.playlists = playlists;
+            // a list fetched before the backend reported a change can't confirm the save
+            let saved = model.pending_save.as_ref().map_or(false, |pending_save| {
+                pending_save.playlists_changed
+                    && playlist_named(&model.playlists, &pending_save.name).is_some()
+            });
+            if saved {
+                if let Some(pending_save) = model.pending_save.take() {
+                    orders.notify(Toast::success(format!(
+                        "Queue saved as playlist \"{}\"",
+                        pending_save.name
+                    )));
+                }
+            }
+        }
+        Msg::PlaylistsFetched(Err(e)) => {
+            log!("Failed to fetch playlists", e);
+            orders.notify(Toast::warning(format!(
+                "Could not check for existing playlists: {}",
+                e
+            )));
         }
         _ => {}
     }
 }
 
-pub fn view(model: &Model) -> Node<Msg> {
+/// Existing playlist that saving under `name` would overwrite.
+fn playlist_named<'a>(playlists: &'a [Playlist], name: &str) -> Option<&'a Playlist> {
+    let name = name.trim();
+    playlists
+        .iter()
+        .find(|playlist| playlist.name.eq_ignore_ascii_case(name))
+}
+
+/// Sends `action` to the backend and applies it to `queue_items` right away,
+/// the queue change event that follows brings the backend state.
+fn apply_action(model: &mut Model, action: QueueAction, orders: &mut impl Orders<Msg>) {
+    model.selected.clear();
+    match action {
+        QueueAction::Remove(songs) => {
+            let indexes: BTreeSet<_> = songs
+                .iter()
+                .filter_map(|song| index_of(&model.queue_items, song))
+                .collect();
+            if indexes.len() < songs.len() {
+                orders.notify(Toast::warning(
+                    "Some of the selected songs were no longer in the queue",
+                ));
+            }
+            // from the end, so removing one song doesn't shift the positions of the others
+            for index in indexes.iter().rev() {
+                if let Some(position) = model.queue_items.get(*index).and_then(|song| song.position)
+                {
+                    orders.send_msg(Msg::SendCommand(Command::QueueRemove(position)));
+                }
+            }
+            edit_keeping_positions(&mut model.queue_items, |items| {
+                let mut index = 0;
+                items.retain(|_| {
+                    index += 1;
+                    !indexes.contains(&(index - 1))
+                });
+            });
+        }
+        QueueAction::Clear => {
+            orders.send_msg(Msg::SendCommand(Command::QueueClear));
+            model.queue_items.clear();
+        }
+        QueueAction::Crop(song) => {
+            let keep = match index_of(&model.queue_items, &song) {
+                Some(keep) => keep,
+                None => {
+                    orders.notify(Toast::warning(
+                        "The playing song changed, nothing was removed",
+                    ));
+                    return;
+                }
+            };
+            orders.send_msg(Msg::SendCommand(Command::QueueCrop));
+            edit_keeping_positions(&mut model.queue_items, |items| {
+                let song = items.swap_remove(keep);
+                *items = vec![song];
+            });
+        }
+    }
+}
+
+/// Index of `song` in `items`, matched by queue position and file.
+fn index_of(items: &[Song], song: &Song) -> Option<usize> {
+    items
+        .iter()
+        .position(|item| item.position == song.position && item.file == song.file)
+}
+
+/// Runs `edit` on `items` and renumbers them with the positions they had before,
+/// so positions stay contiguous like the ones the backend assigns.
+fn edit_keeping_positions(items: &mut Vec<Song>, edit: impl FnOnce(&mut Vec<Song>)) {
+    let positions: Vec<_> = items.iter().map(|song| song.position).collect();
+    edit(items);
+    for (song, position) in items.iter_mut().zip(positions) {
+        song.position = position;
+    }
+}
+
+/// Moves the song at index `from` to index `to`, sends the move to the backend and keeps
+/// the previous order until the backend confirms it with a queue change.
+fn move_item(model: &mut Model, from: usize, to: usize, orders: &mut impl Orders<Msg>) {
+    if from == to || from >= model.queue_items.len() || to >= model.queue_items.len() {
+        return;
+    }
+    let (from_position, to_position) = match (
+        model.queue_items[from].position,
+        model.queue_items[to].position,
+    ) {
+        (Some(from_position), Some(to_position)) => (from_position, to_position),
+        _ => return,
+    };
+    model.selected.clear();
+    let previous_items = model.queue_items.clone();
+    edit_keeping_positions(&mut model.queue_items, |items| {
+        let song = items.remove(from);
+        items.insert(to, song);
+    });
+    model.pending_move = Some(PendingMove {
+        previous_items,
+        _confirmation_timeout: orders
+            .perform_cmd_with_handle(cmds::timeout(COMMAND_TIMEOUT_MS, || Msg::MoveNotConfirmed)),
+    });
+    orders.send_msg(Msg::SendCommand(Command::QueueMove(
+        from_position,
+        to_position,
+    )));
+}
+
+/// Index of the queue row under the given viewport coordinates, used while dragging by touch
+/// because touch events keep targeting the row the drag started on.
+fn queue_index_at(x: i32, y: i32) -> Option<usize> {
+    #[allow(clippy::cast_precision_loss)]
+    let element = document().element_from_point(x as f32, y as f32)?;
+    element
+        .closest(&format!("[{}]", QUEUE_INDEX_ATTR))
+        .ok()
+        .flatten()?
+        .get_attribute(QUEUE_INDEX_ATTR)?
+        .parse()
+        .ok()
+}
+
+/// Scrolls the list so `element` ends up in the middle of the viewport.
+fn scroll_into_view(element: &web_sys::Element) {
+    let options = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&options, &"block".into(), &"center".into());
+    let _ = js_sys::Reflect::set(&options, &"behavior".into(), &"smooth".into());
+    let scroll = js_sys::Reflect::get(element, &"scrollIntoView".into())
+        .ok()
+        .and_then(|scroll| scroll.dyn_into::<js_sys::Function>().ok());
+    if let Some(scroll) = scroll {
+        let _ = scroll.call1(element, &options);
+    }
+}
+
+pub fn view(model: &Model, player_store: &PlayerStore) -> Node<Msg> {
     div![
         crate::view_spinner_modal(model.waiting_response),
-        view_queue_items(model)
+        view_confirmation(model.confirmation.as_ref()),
+        model
+            .save_dialog
+            .as_ref()
+            .map(|dialog| view_save_dialog(dialog, &model.playlists)),
+        view_toolbar(model, player_store),
+        view_queue_items(model, player_store)
     ]
 }
 
-fn view_queue_items(model: &Model) -> Node<Msg> {
+fn view_toolbar(model: &Model, player_store: &PlayerStore) -> Node<Msg> {
+    let current_index = player_store.current_track_info.as_ref().and_then(|track| {
+        model
+            .queue_items
+            .iter()
+            .position(|item| is_current(item, track))
+    });
+    let selected: Vec<Song> = model
+        .selected
+        .iter()
+        .filter_map(|index| model.queue_items.get(*index).cloned())
+        .collect();
     div![
+        C!["buttons", "queue-toolbar"],
+        button![
+            C!["button", "is-small"],
+            attrs! {At::Disabled => selected.is_empty().as_at_value()},
+            span![C!["icon"], i![C!["material-icons"], "delete"]],
+            span![format!("Remove selected ({})", selected.len())],
+            ev(Ev::Click, move |_| Msg::AskConfirmation(
+                QueueAction::Remove(selected)
+            )),
+        ],
+        button![
+            C!["button", "is-small"],
+            attrs! {At::Disabled => current_index.is_none().as_at_value()},
+            span![C!["icon"], i![C!["material-icons"], "content_cut"]],
+            span!["Crop to current"],
+            current_index
+                .and_then(|index| model.queue_items.get(index).cloned())
+                .map(|song| {
+                    ev(Ev::Click, move |_| {
+                        Msg::AskConfirmation(QueueAction::Crop(song))
+                    })
+                }),
+        ],
+        button![
+            C!["button", "is-small", "is-danger", "is-outlined"],
+            attrs! {At::Disabled => model.queue_items.is_empty().as_at_value()},
+            span![C!["icon"], i![C!["material-icons"], "clear_all"]],
+            span!["Clear queue"],
+            ev(Ev::Click, |_| Msg::AskConfirmation(QueueAction::Clear)),
+        ],
+        button![
+            C!["button", "is-small"],
+            attrs! {At::Disabled => model.queue_items.is_empty().as_at_value()},
+            span![C!["icon"], i![C!["material-icons"], "playlist_add"]],
+            span!["Save as playlist"],
+            ev(Ev::Click, |_| Msg::OpenSaveDialog),
+        ],
+    ]
+}
+
+fn view_save_dialog(dialog: &SaveDialog, playlists: &[Playlist]) -> Node<Msg> {
+    let existing = playlist_named(playlists, &dialog.name);
+    let can_save = !dialog.name.trim().is_empty();
+    div![
+        C!["modal", "is-active"],
         div![
-            C![
-                "list",
-                "has-overflow-ellipsis has-visible-pointer-controls has-hoverable-list-items"
-            ],
-            model.queue_items.iter().map(|it| {
-                let cp = it.position.unwrap_or(0);
+            C!["modal-background"],
+            ev(Ev::Click, |_| Msg::SaveDialogClosed)
+        ],
+        div![
+            C!["modal-content"],
+            div![
+                C!["box"],
+                h2![C!["title", "is-5"], "Save queue as playlist"],
                 div![
-                    C!["list-item"],
+                    C!["field"],
+                    label![C!["label"], "Name"],
                     div![
-                        C!["list-item-content"],
-                        div![C!["list-item-title"], &it.get_title()],
-                        div![C!["description"], &it.album],
+                        C!["control"],
+                        input![
+                            C!["input", IF!(existing.is_some() => "is-warning")],
+                            attrs! {
+                                At::Type => "text",
+                                At::Value => dialog.name,
+                                At::Placeholder => "Playlist name",
+                                At::AutoFocus => AtValue::None,
+                            },
+                            input_ev(Ev::Input, Msg::PlaylistNameChanged),
+                            keyboard_ev(Ev::KeyDown, |event| {
+                                IF!(event.key() == "Enter" => Msg::SaveAsPlaylist)
+                            }),
+                        ],
                     ],
-                    div![
-                        C!["list-item-controls"],
-                        div![
-                            C!["buttons"],
-                            button![
-                                C!["button is-light is-small"],
-                                span![C!["icon"], i![C!["fas", "fa-play"]]],
-                                ev(Ev::Click, move |_| Msg::SendCommand(Command::PlayAt(cp))),
-                            ],
+                    existing.map(|playlist| {
+                        p![
+                            C!["help", "is-warning"],
+                            format!(
+                                "A playlist named \"{}\" already exists and will be replaced.",
+                                playlist.name
+                            )
                         ]
-                    ]
-                ]
-            })
-        ]
+                    }),
+                ],
+                div![
+                    C!["buttons", "is-right"],
+                    button![
+                        C!["button"],
+                        "Cancel",
+                        ev(Ev::Click, |_| Msg::SaveDialogClosed)
+                    ],
+                    button![
+                        C![
+                            "button",
+                            if existing.is_some() {
+                                "is-warning"
+                            } else {
+                                "is-primary"
+                            }
+                        ],
+                        attrs! {At::Disabled => (!can_save).as_at_value()},
+                        if existing.is_some() {
+                            "Replace"
+                        } else {
+                            "Save"
+                        },
+                        ev(Ev::Click, |_| Msg::SaveAsPlaylist)
+                    ],
+                ],
+            ],
+        ],
+    ]
+}
+
+fn view_confirmation(action: Option<&QueueAction>) -> Node<Msg> {
+    let action = match action {
+        Some(action) => action,
+        None => return empty![],
+    };
+    div![
+        C!["modal", "is-active"],
+        div![
+            C!["modal-background"],
+            ev(Ev::Click, |_| Msg::ActionCancelled)
+        ],
+        div![
+            C!["modal-content"],
+            div![
+                C!["box"],
+                p![C!["mb-4"], action.question()],
+                div![
+                    C!["buttons", "is-right"],
+                    button![
+                        C!["button"],
+                        "Cancel",
+                        ev(Ev::Click, |_| Msg::ActionCancelled)
+                    ],
+                    button![
+                        C!["button", "is-danger"],
+                        "Remove",
+                        ev(Ev::Click, |_| Msg::ActionConfirmed)
+                    ],
+                ],
+            ],
+        ],
+    ]
+}
+
+/// Whether `item` is the queue entry of `current_track`, by position when the player
+/// reports one, so a song queued twice is only highlighted once.
+fn is_current(item: &Song, current_track: &Song) -> bool {
+    match (item.position, current_track.position) {
+        (Some(position), Some(current_position)) => position == current_position,
+        _ => item.file == current_track.file,
+    }
+}
+
+fn view_elapsed(player_store: &PlayerStore) -> Node<Msg> {
+    let (elapsed, total) = match (player_store.elapsed_secs(), player_store.total_secs()) {
+        (Some(elapsed), Some(total)) => (elapsed, total),
+        _ => return empty![],
+    };
+    span![
+        C!["queue-elapsed", "is-size-7"],
+        if total > 0 {
+            format!("{} / {}", format_secs(elapsed), format_secs(total))
+        } else {
+            format_secs(elapsed)
+        }
     ]
 }
 
-pub async fn get_queue_items() -> fetch::Result<Vec<Song>> {
-    Request::new("/api/queue".to_string())
-        .method(Method::Get)
-        .fetch()
-        .await?
-        .check_status()?
-        .json::<Vec<Song>>()
-        .await
+fn view_playing_indicator(playing: bool) -> Node<Msg> {
+    span![
+        C!["playing-indicator", IF!(!playing => "is-paused")],
+        span![],
+        span![],
+        span![],
+    ]
+}
+
+fn view_drag_events(index: usize) -> Vec<EventHandler<Msg>> {
+    vec![
+        drag_ev(Ev::DragStart, move |event| {
+            // Firefox doesn't start a drag without data
+            if let Some(data_transfer) = event.data_transfer() {
+                let _ = data_transfer.set_data("text/plain", &index.to_string());
+                data_transfer.set_effect_allowed("move");
+            }
+            Msg::DragStarted(index)
+        }),
+        drag_ev(Ev::DragOver, move |event| {
+            // allows dropping on this row
+            event.prevent_default();
+            Msg::DraggedOver(index)
+        }),
+        drag_ev(Ev::Drop, |event| {
+            event.prevent_default();
+            Msg::Dropped
+        }),
+        drag_ev(Ev::DragEnd, |_| Msg::DragCancelled),
+    ]
+}
+
+/// Handle for dragging on touch screens, which don't fire drag events.
+fn view_drag_handle(index: usize) -> Node<Msg> {
+    span![
+        C!["icon", "drag-handle"],
+        i![C!["material-icons"], "drag_handle"],
+        touch_ev(Ev::TouchStart, move |_| Msg::DragStarted(index)),
+        touch_ev(Ev::TouchMove, |event| {
+            // keeps the page from scrolling while dragging
+            event.prevent_default();
+            event
+                .touches()
+                .get(0)
+                .map(|touch| Msg::TouchDragMoved(touch.client_x(), touch.client_y()))
+        }),
+        touch_ev(Ev::TouchEnd, |_| Msg::Dropped),
+        touch_ev(Ev::TouchCancel, |_| Msg::DragCancelled),
+    ]
+}
+
+fn view_queue_items(model: &Model, player_store: &PlayerStore) -> Node<Msg> {
+    let current_track = player_store.current_track_info.as_ref();
+    // rows are keyed by song so that after a refresh the unchanged ones are moved, not redrawn
+    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+    let drag = model.drag;
+    div![div![
+        C![
+            "list",
+            "has-overflow-ellipsis has-visible-pointer-controls has-hoverable-list-items"
+        ],
+        model.queue_items.iter().enumerate().map(|(index, it)| {
+            let cp = it.position.unwrap_or(0);
+            let current = current_track.map_or(false, |track| is_current(it, track));
+            let occurrence = occurrences.entry(it.file.as_str()).or_default();
+            *occurrence += 1;
+            // marks where the dragged row would end up when dropped here
+            let drop_class = drag.and_then(|drag| match drag.from {
+                from if from == index => Some("is-dragging"),
+                from if drag.over == index && from < index => Some("is-drop-after"),
+                _ if drag.over == index => Some("is-drop-before"),
+                _ => None,
+            });
+            div![
+                el_key(&format!("{}#{}", it.file, occurrence)),
+                C!["list-item", IF!(current => "is-active"), drop_class],
+                IF!(current => el_ref(&model.playing_item)),
+                attrs! {
+                    At::Draggable => "true",
+                    At::from(QUEUE_INDEX_ATTR) => index,
+                },
+                view_drag_events(index),
+                label![
+                    C!["checkbox", "queue-select"],
+                    input![
+                        attrs! {
+                            At::Type => "checkbox",
+                            At::Checked => model.selected.contains(&index).as_at_value(),
+                        },
+                        ev(Ev::Change, move |_| Msg::ToggleSelected(index)),
+                    ],
+                ],
+                view_drag_handle(index),
+                album_art::view_thumbnail(&model.thumbnails, it),
+                div![
+                    C!["list-item-content"],
+                    div![
+                        C!["list-item-title"],
+                        IF!(current => view_playing_indicator(player_store.is_playing())),
+                        &it.get_title()
+                    ],
+                    div![C!["description"], &it.album],
+                ],
+                div![
+                    C!["list-item-controls"],
+                    div![
+                        C!["buttons"],
+                        IF!(current => view_elapsed(player_store)),
+                        button![
+                            C!["button is-light is-small"],
+                            span![C!["icon"], i![C!["fas", "fa-play"]]],
+                            ev(Ev::Click, move |_| Msg::SendCommand(Command::PlayAt(cp))),
+                        ],
+                        button![
+                            C!["button is-light is-small"],
+                            attrs! {At::Title => "Remove from queue"},
+                            span![C!["icon"], i![C!["fas", "fa-times"]]],
+                            ev(Ev::Click, move |_| Msg::RemoveItem(index)),
+                        ],
+                    ]
+                ]
+            ]
+        })
+    ]]
 }
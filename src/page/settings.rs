@@ -7,18 +7,17 @@ use seed::{prelude::*, *};
 use std::str::FromStr;
 use strum::IntoEnumIterator;
 
+use crate::api;
+use crate::toast::Toast;
+use crate::ui_settings::{self, UiSettings};
 use crate::Urls;
 
-const API_SETTINGS_PATH: &str = "/api/settings";
-const API_SPOTIFY_IS_AUTH_PATH: &str = "/api/spotify/is-authorized";
-const API_SPOTIFY_GET_AUTH_URL_PATH: &str = "/api/spotify/get-url";
-const API_SPOTIFY_GET_ACCOUNT_INFO_PATH: &str = "/api/spotify/me";
-
 // ------ ------
 //     Model
 #[derive(Debug)]
 pub struct Model {
     settings: Settings,
+    ui_settings: UiSettings,
     waiting_response: bool,
     spotify_is_authorized: bool,
     spotify_auth_url: Option<String>,
@@ -48,20 +47,23 @@ pub enum Msg {
     InputSpotifyAuthCallbackUrl(String),
     InputSpotifyAlsaDeviceName(String),
 
-    SpotifyIsAuthorizedFetched(String),
-    SpotifyAccountInfoFetched(SpotifyAccountInfo),
-    SpotifyAuthorizationUrlFetched(String),
+    SpotifyIsAuthorizedFetched(api::Result<bool>),
+    SpotifyAccountInfoFetched(api::Result<SpotifyAccountInfo>),
+    SpotifyAuthorizationUrlFetched(api::Result<String>),
 
     InputAlsaDeviceChanged(String),
 
     InputDacFilterChanged(FilterType),
     InputDacGainLevelChanged(GainLevel),
 
+    InputSeekStepChanged(String),
+    ToggleOnlineAlbumArt,
+
     // --- Buttons ----
     SaveSettings,
-    SettingsSaved(fetch::Result<Settings>),
+    SettingsSaved(api::Result<Settings>),
 
-    RemoteConfiguration(Settings),
+    RemoteConfiguration(api::Result<Settings>),
     SendCommand(Command),
 }
 
@@ -70,39 +72,15 @@ pub enum Msg {
 // ------ ------
 pub(crate) fn init(_url: Url, orders: &mut impl Orders<Msg>) -> Model {
     log!("Settings Init called");
+    orders.perform_cmd(async { Msg::RemoteConfiguration(api::get_settings().await) });
+    orders
+        .perform_cmd(async { Msg::SpotifyIsAuthorizedFetched(api::spotify_is_authorized().await) });
     orders.perform_cmd(async {
-        let response = fetch(API_SETTINGS_PATH)
-            .await
-            .expect("Failed to get settings from dplayer backend");
-
-        let sett = response
-            .json::<Settings>()
-            .await
-            .expect("failed to deserialize to Configuration");
-        Msg::RemoteConfiguration(sett)
-    });
-    orders.perform_cmd(async {
-        Msg::SpotifyIsAuthorizedFetched(
-            fetch(API_SPOTIFY_IS_AUTH_PATH)
-                .await
-                .expect("")
-                .text()
-                .await
-                .unwrap(),
-        )
-    });
-    orders.perform_cmd(async {
-        Msg::SpotifyAccountInfoFetched(
-            fetch(API_SPOTIFY_GET_ACCOUNT_INFO_PATH)
-                .await
-                .expect("")
-                .json::<SpotifyAccountInfo>()
-                .await
-                .unwrap(),
-        )
+        Msg::SpotifyAccountInfoFetched(api::get_spotify_account_info().await)
     });
     Model {
         settings: Settings::default(),
+        ui_settings: ui_settings::load(),
         waiting_response: false,
         spotify_is_authorized: false,
         spotify_auth_url: None,
@@ -119,7 +97,9 @@ pub(crate) fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>)
         Msg::SaveSettings => {
             // todo: show modal wait window while server is restarting. use ws status.
             let settings = model.settings.clone();
-            orders.perform_cmd(async { Msg::SettingsSaved(save_settings(settings).await) });
+            orders.perform_cmd(
+                async move { Msg::SettingsSaved(api::save_settings(&settings).await) },
+            );
             model.waiting_response = true;
         }
         Msg::SelectActivePlayer(value) => {
@@ -174,51 +154,71 @@ pub(crate) fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>)
         Msg::InputDacGainLevelChanged(g) => {
             model.settings.dac_settings.gain = g;
         }
-        Msg::SpotifyIsAuthorizedFetched(result) => {
-            log!("Auth result fetched", result);
-            if result == "true" {
-                model.spotify_is_authorized = true;
-            } else {
-                model.spotify_is_authorized = false;
+        Msg::ToggleOnlineAlbumArt => {
+            model.ui_settings.online_album_art = !model.ui_settings.online_album_art;
+            save_ui_settings(model, orders);
+        }
+        Msg::InputSeekStepChanged(value) => {
+            if let Ok(step) = value.parse::<u64>() {
+                model.ui_settings.seek_step_secs = step.max(1);
+                save_ui_settings(model, orders);
+            }
+        }
+        Msg::SpotifyIsAuthorizedFetched(Ok(authorized)) => {
+            log!("Auth result fetched", authorized);
+            model.spotify_is_authorized = authorized;
+            if !authorized {
                 orders.perform_cmd(async {
-                    Msg::SpotifyAuthorizationUrlFetched(
-                        fetch(API_SPOTIFY_GET_AUTH_URL_PATH)
-                            .await
-                            .expect("")
-                            .text()
-                            .await
-                            .expect(""),
-                    )
+                    Msg::SpotifyAuthorizationUrlFetched(api::get_spotify_auth_url().await)
                 });
             }
         }
-        Msg::SpotifyAuthorizationUrlFetched(value) => {
+        Msg::SpotifyIsAuthorizedFetched(Err(e)) => {
+            log!("Failed to fetch Spotify authorization", e);
+        }
+        Msg::SpotifyAuthorizationUrlFetched(Ok(value)) => {
             log!("Url fetched", value);
             model.spotify_auth_url = Some(value);
         }
-        Msg::SpotifyAccountInfoFetched(info) => {
+        Msg::SpotifyAuthorizationUrlFetched(Err(e)) => {
+            log!("Failed to fetch Spotify authorization url", e);
+            orders.notify(Toast::error(format!(
+                "Failed to get the Spotify authorization link: {}",
+                e
+            )));
+        }
+        Msg::SpotifyAccountInfoFetched(Ok(info)) => {
             model.spotify_account_info = Some(info);
         }
-        Msg::RemoteConfiguration(sett) => {
+        // expected while Spotify is not authorized yet
+        Msg::SpotifyAccountInfoFetched(Err(e)) => log!("Spotify account info not available", e),
+        Msg::RemoteConfiguration(Ok(sett)) => {
             model.settings = sett;
         }
+        Msg::RemoteConfiguration(Err(e)) => {
+            log!("Failed to fetch settings", e);
+            orders.notify(Toast::error(format!("Failed to load settings: {}", e)));
+        }
         Msg::SettingsSaved(saved) => {
-            log!("Saved settings with result {}", saved);
             model.waiting_response = false;
+            match saved {
+                Ok(_) => {
+                    orders.notify(Toast::success("Settings saved"));
+                }
+                Err(e) => {
+                    log!("Failed to save settings", e);
+                    orders.notify(Toast::error(format!("Failed to save settings: {}", e)));
+                }
+            }
         }
         _ => {}
     }
 }
 
-async fn save_settings(settings: Settings) -> fetch::Result<Settings> {
-    Request::new(API_SETTINGS_PATH)
-        .method(Method::Post)
-        .json(&settings)?
-        .fetch()
-        .await?
-        .check_status()?
-        .json::<Settings>()
-        .await
+/// Browser-only settings take effect right away, saving the backend ones restarts the server.
+fn save_ui_settings(model: &Model, orders: &mut impl Orders<Msg>) {
+    ui_settings::save(&model.ui_settings);
+    orders.notify(model.ui_settings.clone());
 }
 
 // ------ ------
@@ -373,6 +373,7 @@ fn view_settings(model: &Model) -> Node<Msg> {
             ],
             IF!(settings.dac_settings.enabled => view_dac(&settings.dac_settings))
         ],
+        view_ui_settings(&model.ui_settings),
         div![
             C!["field", "is-grouped"],
             div![
@@ -395,6 +396,54 @@ fn view_settings(model: &Model) -> Node<Msg> {
     ]
 }
 
+fn view_ui_settings(ui_settings: &UiSettings) -> Node<Msg> {
+    section![
+        C!["section"],
+        h1![C!["title"], "User interface"],
+        div![
+            C!["field"],
+            ev(Ev::Click, |_| Msg::ToggleOnlineAlbumArt),
+            input![
+                C!["switch"],
+                attrs! {
+                    At::Name => "online_art_cb"
+                    At::Type => "checkbox"
+                    At::Checked => ui_settings.online_album_art.as_at_value(),
+                },
+            ],
+            label![
+                C!["label"],
+                "Look up missing album art online?",
+                attrs! {
+                    At::For => "online_art_cb"
+                }
+            ]
+        ],
+        div![
+            C!["field", "is-horizontal"],
+            div![
+                C!["field-label", "is-small"],
+                label!["Seek step (seconds)", C!["label"]],
+            ],
+            div![
+                C!["field-body"],
+                div![
+                    C!["field"],
+                    input![
+                        C!["input"],
+                        attrs! {
+                            At::Type => "number"
+                            At::Min => 1
+                            At::Value => ui_settings.seek_step_secs
+                        },
+                        input_ev(Ev::Input, Msg::InputSeekStepChanged),
+                    ],
+                ]
+            ],
+        ],
+    ]
+}
+
 fn view_dac(dac_settings: &DacSettings) -> Node<Msg> {
     div![
         div![
@@ -0,0 +1,136 @@
+use std::fmt;
+
+use api_models::state::StateChangeEvent;
+use serde_json::Value;
+
+/// Highest protocol envelope version this UI understands.
+///
+/// Frames may either be a bare `StateChangeEvent` (treated as version 1) or an envelope
+/// like `{"version": 1, "event": {...}}`, which lets the backend announce newer formats.
+pub const PROTOCOL_VERSION: u64 = 1;
+
+/// Keepalive frame sent by the UI. Backends that support it answer every one with
+/// `PONG_FRAME`, older ones don't answer at all, so the UI only relies on pongs
+/// after it has seen one on the current connection.
+pub const PING_FRAME: &str = "ping";
+pub const PONG_FRAME: &str = "pong";
+
+#[derive(Debug, Clone)]
+pub enum DecodeError {
+    /// Frame is not valid (UTF-8) JSON or has a broken envelope.
+    Malformed(String),
+    /// Frame is valid JSON, but not an event this UI knows about.
+    UnknownEvent(String),
+    /// Frame is wrapped in an envelope with a newer version than `PROTOCOL_VERSION`.
+    UnsupportedVersion(u64),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(reason) => write!(f, "malformed frame: {}", reason),
+            Self::UnknownEvent(reason) => write!(f, "unknown event: {}", reason),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported protocol version {} (supported up to {})",
+                version, PROTOCOL_VERSION
+            ),
+        }
+    }
+}
+
+pub fn decode_text(text: &str) -> Result<StateChangeEvent, DecodeError> {
+    let value: Value =
+        serde_json::from_str(text).map_err(|e| DecodeError::Malformed(e.to_string()))?;
+    let event = match value {
+        Value::Object(mut envelope) if envelope.contains_key("version") => {
+            let version = envelope
+                .get("version")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| DecodeError::Malformed("invalid envelope version".to_string()))?;
+            if version > PROTOCOL_VERSION {
+                return Err(DecodeError::UnsupportedVersion(version));
+            }
+            envelope
+                .remove("event")
+                .ok_or_else(|| DecodeError::Malformed("envelope without event".to_string()))?
+        }
+        value => value,
+    };
+    serde_json::from_value(event).map_err(|e| DecodeError::UnknownEvent(e.to_string()))
+}
+
+pub fn decode_bytes(bytes: &[u8]) -> Result<StateChangeEvent, DecodeError> {
+    let text = std::str::from_utf8(bytes).map_err(|e| DecodeError::Malformed(e.to_string()))?;
+    decode_text(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    // run by `wasm-pack test`, e.g. `cargo make test_h_firefox`
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn error_event_json() -> String {
+        serde_json::to_string(&StateChangeEvent::Error("boom".to_string())).unwrap()
+    }
+
+    fn assert_error_event(decoded: Result<StateChangeEvent, DecodeError>) {
+        match decoded {
+            Ok(StateChangeEvent::Error(message)) => assert_eq!(message, "boom"),
+            other => panic!("unexpected decode result: {:?}", other),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn decodes_bare_event() {
+        assert_error_event(decode_text(&error_event_json()));
+    }
+
+    #[wasm_bindgen_test]
+    fn decodes_v1_envelope() {
+        let frame = format!(r#"{{"version": 1, "event": {}}}"#, error_event_json());
+        assert_error_event(decode_text(&frame));
+        assert_error_event(decode_bytes(frame.as_bytes()));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_newer_version() {
+        let frame = format!(r#"{{"version": 2, "event": {}}}"#, error_event_json());
+        assert!(matches!(
+            decode_text(&frame),
+            Err(DecodeError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_envelope_without_event() {
+        assert!(matches!(
+            decode_text(r#"{"version": 1}"#),
+            Err(DecodeError::Malformed(_))
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_malformed_input() {
+        assert!(matches!(
+            decode_text("{not json"),
+            Err(DecodeError::Malformed(_))
+        ));
+        assert!(matches!(
+            decode_text(r#"{"version": "one", "event": {}}"#),
+            Err(DecodeError::Malformed(_))
+        ));
+        assert!(matches!(
+            decode_bytes(&[0xff, 0xfe]),
+            Err(DecodeError::Malformed(_))
+        ));
+        assert!(matches!(
+            decode_text(r#"{"NoSuchEvent": 1}"#),
+            Err(DecodeError::UnknownEvent(_))
+        ));
+    }
+}
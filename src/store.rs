@@ -0,0 +1,138 @@
+use api_models::common::*;
+use api_models::player::*;
+use api_models::state::*;
+
+/// Latest player state received from the backend, shared by all pages.
+///
+/// It is updated from every `StateChangeEvent` in the root `update`, the event itself is
+/// then broadcast with `orders.notify` so pages can subscribe to the changes they need.
+#[derive(Debug, Default)]
+pub struct PlayerStore {
+    pub current_track_info: Option<Song>,
+    pub player_info: Option<PlayerInfo>,
+    pub streamer_state: Option<StreamerState>,
+    // last volume reported by the backend while an optimistic `SetVol` is unconfirmed
+    confirmed_volume: Option<i64>,
+    // resolved cover of `current_track_info`, `None` while it is looked up or when it has none
+    pub album_art: Option<String>,
+    // when `player_info` arrived, its elapsed time is only exact at that moment
+    player_info_received_at: f64,
+    // advanced by `tick` while playing, so the elapsed time can be interpolated
+    now: f64,
+    // `player_info` still describes the previous track, until the backend reports the new one
+    player_info_outdated: bool,
+}
+
+impl PlayerStore {
+    pub fn apply(&mut self, event: StateChangeEvent) {
+        match event {
+            StateChangeEvent::CurrentTrackInfoChanged(track_info) => {
+                if self.current_track_file() != Some(track_info.file.as_str()) {
+                    self.album_art = None;
+                    self.player_info_outdated = self.player_info.is_some();
+                }
+                self.current_track_info = Some(track_info);
+            }
+            StateChangeEvent::PlayerInfoChanged(player_info) => {
+                self.player_info = Some(player_info);
+                self.player_info_outdated = false;
+                self.player_info_received_at = js_sys::Date::now();
+                self.now = self.player_info_received_at;
+            }
+            StateChangeEvent::StreamerStateChanged(streamer_state) => {
+                self.confirmed_volume = None;
+                self.streamer_state = Some(streamer_state);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.player_info.as_ref().map_or(false, |f| {
+            f.state
+                .as_ref()
+                .map_or(false, |f| *f == PlayerState::PLAYING)
+        })
+    }
+
+    pub fn current_track_file(&self) -> Option<&str> {
+        self.current_track_info
+            .as_ref()
+            .map(|track_info| track_info.file.as_str())
+    }
+
+    /// Stores the cover resolved for `file`, unless the track changed during the lookup.
+    pub fn set_album_art(&mut self, file: &str, album_art: Option<String>) -> bool {
+        if self.current_track_file() != Some(file) {
+            return false;
+        }
+        self.album_art = album_art;
+        true
+    }
+
+    pub fn tick(&mut self) {
+        self.now = js_sys::Date::now();
+    }
+
+    /// Elapsed seconds of the current track, advanced from the last reported position
+    /// while playing. `None` after a track change until its first position is reported.
+    pub fn elapsed_secs(&self) -> Option<u64> {
+        let player_info = self.current_player_info()?;
+        let mut elapsed = player_info.time.0.as_secs_f64();
+        if self.is_playing() {
+            elapsed += (self.now - self.player_info_received_at).max(0.0) / 1000.0;
+        }
+        let total = player_info.time.1.as_secs_f64();
+        if total > 0.0 {
+            elapsed = elapsed.min(total);
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Some(elapsed as u64)
+    }
+
+    pub fn total_secs(&self) -> Option<u64> {
+        self.current_player_info()
+            .map(|player_info| player_info.time.1.as_secs())
+    }
+
+    fn current_player_info(&self) -> Option<&PlayerInfo> {
+        self.player_info
+            .as_ref()
+            .filter(|_| !self.player_info_outdated)
+    }
+
+    /// Optimistically applies a command before the backend confirms it.
+    pub fn apply_command(&mut self, command: &Command) {
+        if let Command::SetVol(vol) = command {
+            if let Some(streamer_state) = self.streamer_state.as_mut() {
+                let volume_state = &mut streamer_state.volume_state;
+                self.confirmed_volume.get_or_insert(volume_state.volume);
+                volume_state.volume = i64::from(*vol);
+            }
+        }
+    }
+
+    /// Goes back to the backend's volume after an optimistic `SetVol` was never delivered.
+    pub fn revert_volume(&mut self) {
+        if let (Some(volume), Some(streamer_state)) =
+            (self.confirmed_volume.take(), self.streamer_state.as_mut())
+        {
+            streamer_state.volume_state.volume = volume;
+        }
+    }
+}
+
+/// Splits a status snapshot into the events that would have produced it.
+pub fn events_from_last_state(last_state: LastState) -> Vec<StateChangeEvent> {
+    let mut events = Vec::new();
+    if let Some(track_info) = last_state.current_track_info {
+        events.push(StateChangeEvent::CurrentTrackInfoChanged(track_info));
+    }
+    if let Some(player_info) = last_state.player_info {
+        events.push(StateChangeEvent::PlayerInfoChanged(player_info));
+    }
+    if let Some(streamer_state) = last_state.streamer_state {
+        events.push(StateChangeEvent::StreamerStateChanged(streamer_state));
+    }
+    events
+}
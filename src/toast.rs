@@ -0,0 +1,98 @@
+use seed::{prelude::*, *};
+
+const MAX_VISIBLE_TOASTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// A notification shown on top of every page.
+///
+/// Pages push one with `orders.notify(Toast::error(..))`, the root model subscribes to them.
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub level: Level,
+    pub message: String,
+}
+
+impl Toast {
+    pub fn info(message: impl Into<String>) -> Self {
+        Self::new(Level::Info, message)
+    }
+    pub fn success(message: impl Into<String>) -> Self {
+        Self::new(Level::Success, message)
+    }
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Level::Warning, message)
+    }
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Level::Error, message)
+    }
+
+    fn new(level: Level, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            message: message.into(),
+        }
+    }
+
+    /// How long the toast stays visible unless closed manually.
+    pub const fn timeout_ms(&self) -> u32 {
+        match self.level {
+            Level::Error => 8_000,
+            Level::Warning => 6_000,
+            Level::Info | Level::Success => 3_000,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Toasts {
+    next_id: u64,
+    visible: Vec<(u64, Toast)>,
+}
+
+impl Toasts {
+    /// Adds a toast and returns its id, dropping the oldest ones beyond the visible limit.
+    pub fn push(&mut self, toast: Toast) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.visible.push((id, toast));
+        if self.visible.len() > MAX_VISIBLE_TOASTS {
+            self.visible.remove(0);
+        }
+        id
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.visible.retain(|(toast_id, _)| *toast_id != id);
+    }
+}
+
+pub fn view<Ms: 'static>(
+    toasts: &Toasts,
+    on_close: impl FnOnce(u64) -> Ms + Clone + 'static,
+) -> Node<Ms> {
+    div![
+        C!["toasts"],
+        toasts.visible.iter().map(|(id, toast)| {
+            let id = *id;
+            let on_close = on_close.clone();
+            let color = match toast.level {
+                Level::Info => "is-info",
+                Level::Success => "is-success",
+                Level::Warning => "is-warning",
+                Level::Error => "is-danger",
+            };
+            div![
+                C!["notification", color],
+                button![C!["delete"], ev(Ev::Click, move |_| on_close(id))],
+                &toast.message,
+            ]
+        })
+    ]
+}
@@ -0,0 +1,31 @@
+use seed::{prelude::*, *};
+use serde::{Deserialize, Serialize};
+
+const UI_SETTINGS_STORAGE_KEY: &str = "dplayer-ui-settings";
+
+/// Settings that only affect this browser, kept in `localStorage` next to the backend ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiSettings {
+    pub seek_step_secs: u64,
+    pub online_album_art: bool,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            seek_step_secs: 10,
+            online_album_art: false,
+        }
+    }
+}
+
+pub fn load() -> UiSettings {
+    LocalStorage::get(UI_SETTINGS_STORAGE_KEY).unwrap_or_default()
+}
+
+pub fn save(ui_settings: &UiSettings) {
+    if let Err(e) = LocalStorage::insert(UI_SETTINGS_STORAGE_KEY, ui_settings) {
+        log!("Failed to save ui settings", e);
+    }
+}